@@ -0,0 +1,414 @@
+//! Editorial endpoints under `/api/admin`, for reviewing puzzles before
+//! they're served. Every request needs `Authorization: Bearer <token>`
+//! matching the configured `admin.token`.
+
+use std::future::{ready, Ready};
+
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, Scope};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::db::Repo;
+use crate::error::{ApiError, ApiResult};
+use crate::generation::{
+    assess, build_puzzle_for_date, load_todays_puzzle, Sources, GENERATION_LOCK,
+};
+use crate::grid::{Direction, Grid};
+use crate::models::{ClueWord, DailyPuzzle, PuzzleStatus};
+
+/// The token admin requests must present.
+pub struct AdminToken(pub String);
+
+/// Proof that a request carried the admin token. Handlers take this as an
+/// argument, so unauthenticated requests never reach them.
+pub struct Admin;
+
+impl FromRequest for Admin {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let expected = req.app_data::<web::Data<AdminToken>>();
+        let given = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        ready(match (expected, given) {
+            (Some(expected), Some(given)) if same_token(&expected.0, given) => Ok(Admin),
+            _ => Err(ApiError::Unauthorized),
+        })
+    }
+}
+
+/// Compares tokens without bailing out at the first differing byte, so
+/// response times don't give away how much of a guess was right.
+fn same_token(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+pub fn scope() -> Scope {
+    web::scope("/api/admin")
+        .route("/puzzles", web::get().to(list_upcoming))
+        .route("/puzzle/{date}/regenerate", web::post().to(regenerate))
+        .route("/puzzle/{date}/approve", web::post().to(approve))
+        .route("/puzzle/{date}/publish", web::post().to(publish))
+        .route(
+            "/puzzle/{date}/{direction}/{position}",
+            web::put().to(replace_entry),
+        )
+        .route("/clue/{id}/ban", web::put().to(ban_clue))
+        .route("/clue/{id}/ban", web::delete().to(unban_clue))
+}
+
+fn load(repo: &Repo, date: &str) -> ApiResult<DailyPuzzle> {
+    load_todays_puzzle(repo, date)?
+        .ok_or_else(|| ApiError::NotFound(format!("no puzzle for {}", date)))
+}
+
+fn editable(repo: &Repo, date: &str) -> ApiResult<DailyPuzzle> {
+    let puzzle = load(repo, date)?;
+    if puzzle.status == PuzzleStatus::Published {
+        return Err(ApiError::Conflict(format!(
+            "puzzle for {} is already published",
+            date
+        )));
+    }
+    Ok(puzzle)
+}
+
+/// Every puzzle from today onwards, answers and all, earliest first.
+async fn list_upcoming(_: Admin, repo: web::Data<Repo>) -> ApiResult<HttpResponse> {
+    let from = config::get().puzzle.today().format("%Y-%m-%d").to_string();
+    let puzzles = web::block(move || -> ApiResult<Vec<DailyPuzzle>> {
+        repo.upcoming_dates(&from)?
+            .iter()
+            .map(|date| load(&repo, date))
+            .collect()
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(puzzles))
+}
+
+/// Throws away an unpublished puzzle and generates a different one for its
+/// date, as a new draft (or approved, if review is off). A past date must
+/// already have a puzzle: filling a gap in the archive would renumber it.
+async fn regenerate(
+    _: Admin,
+    repo: web::Data<Repo>,
+    path: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let day = NaiveDate::parse_from_str(&path, "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest(format!("invalid date: {}", path)))?;
+    let past = day < config::get().puzzle.today();
+    let date = day.format("%Y-%m-%d").to_string();
+    let puzzle = web::block(move || -> ApiResult<DailyPuzzle> {
+        let _guard = GENERATION_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let grid = match load_todays_puzzle(&repo, &date)? {
+            Some(puzzle) if puzzle.status == PuzzleStatus::Published => {
+                return Err(ApiError::Conflict(format!(
+                    "puzzle for {} is already published",
+                    date
+                )));
+            }
+            Some(puzzle) => puzzle.grid,
+            None if past => {
+                return Err(ApiError::BadRequest(format!(
+                    "no puzzle for {}, and a new one in the past would renumber the archive",
+                    date
+                )));
+            }
+            None => Grid::daily(),
+        };
+
+        // A fresh label so the seeds, and so the puzzle, differ from last
+        // time. The winning seed is still recorded.
+        let label = format!("{}~{:08x}", date, rand::random::<u32>());
+        let sources = Sources::load(&repo)?;
+        let puzzle = build_puzzle_for_date(&repo, &date, &label, &grid, &sources)?;
+        if !repo.replace_puzzle(&puzzle)? {
+            return Err(ApiError::Conflict(format!(
+                "puzzle for {} was published while regenerating",
+                date
+            )));
+        }
+        load(&repo, &date)
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(puzzle))
+}
+
+async fn approve(
+    _: Admin,
+    repo: web::Data<Repo>,
+    path: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    set_status(repo, path.into_inner(), PuzzleStatus::Approved).await
+}
+
+async fn publish(
+    _: Admin,
+    repo: web::Data<Repo>,
+    path: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    set_status(repo, path.into_inner(), PuzzleStatus::Published).await
+}
+
+async fn set_status(
+    repo: web::Data<Repo>,
+    date: String,
+    status: PuzzleStatus,
+) -> ApiResult<HttpResponse> {
+    let puzzle = web::block(move || -> ApiResult<DailyPuzzle> {
+        let puzzle = editable(&repo, &date)?;
+        let from = [PuzzleStatus::Draft, PuzzleStatus::Approved];
+        if !repo.set_status(&date, &from, status)? {
+            return Err(ApiError::Conflict(format!(
+                "puzzle for {} was published meanwhile",
+                date
+            )));
+        }
+        log::info!(
+            "Puzzle for {} moved from {} to {}",
+            date,
+            puzzle.status.as_str(),
+            status.as_str()
+        );
+        load(&repo, &date)
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(puzzle))
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplaceEntry {
+    clue_word_id: i64,
+}
+
+/// Puts a different clue-word pair in one entry of an unpublished puzzle:
+/// another clue for the same word, or a different word that fits the
+/// crossing letters. `position` counts entries of that direction from 0.
+async fn replace_entry(
+    _: Admin,
+    repo: web::Data<Repo>,
+    path: web::Path<(String, Direction, usize)>,
+    body: web::Json<ReplaceEntry>,
+) -> ApiResult<HttpResponse> {
+    let (date, direction, position) = path.into_inner();
+    let clue_word_id = body.clue_word_id;
+
+    let puzzle = web::block(move || -> ApiResult<DailyPuzzle> {
+        let puzzle = editable(&repo, &date)?;
+        let clue_word = repo
+            .load_clue(clue_word_id)?
+            .ok_or_else(|| ApiError::NotFound(format!("no clue with id {}", clue_word_id)))?;
+        if repo.is_banned(clue_word_id)? {
+            return Err(ApiError::BadRequest(format!(
+                "clue {} is banned",
+                clue_word_id
+            )));
+        }
+        let blocklist =
+            repo.load_blocklist(config::get().moderation.blocklist_categories.as_deref())?;
+        if let Some(blocked) = blocklist
+            .check(&clue_word.word)
+            .or_else(|| blocklist.check(&clue_word.clue))
+        {
+            return Err(ApiError::BadRequest(format!(
+                "clue {} matches blocklist {}",
+                clue_word_id, blocked
+            )));
+        }
+
+        let (mut across_words, mut down_words) = (puzzle.across_words, puzzle.down_words);
+        let words = match direction {
+            Direction::Across => &mut across_words,
+            Direction::Down => &mut down_words,
+        };
+        let Some(slot) = words.get_mut(position) else {
+            return Err(ApiError::NotFound(format!(
+                "puzzle for {} has no {} entry {}",
+                date,
+                direction_name(direction),
+                position
+            )));
+        };
+        *slot = clue_word;
+        let changed = match direction {
+            Direction::Across => position,
+            Direction::Down => across_words.len() + position,
+        };
+        check_fit(&puzzle.grid, &across_words, &down_words, changed)?;
+
+        let sources = Sources::load(&repo)?;
+        // The hints come from the across answers, so a new one may leave
+        // the plusword too hard to pin down.
+        if direction == Direction::Across {
+            let candidates =
+                sources.plusword_candidates(&puzzle.grid, &across_words, &puzzle.plusword);
+            let max_candidates = config::get().generation.max_plusword_candidates;
+            if candidates.len() > max_candidates {
+                return Err(ApiError::BadRequest(format!(
+                    "with {} the plusword's hints leave {} candidates, more than {}",
+                    across_words[position].word,
+                    candidates.len(),
+                    max_candidates
+                )));
+            }
+        }
+        let factors = assess(
+            &puzzle.grid,
+            &across_words,
+            &down_words,
+            &puzzle.plusword,
+            &sources,
+        )?;
+        if !repo.replace_entry(&date, direction, position, clue_word_id, factors.rating())? {
+            return Err(ApiError::Conflict(format!(
+                "puzzle for {} was published meanwhile",
+                date
+            )));
+        }
+        load(&repo, &date)
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(puzzle))
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Across => "across",
+        Direction::Down => "down",
+    }
+}
+
+/// Fails unless every answer is the right length and agrees with the
+/// answers crossing it. The answer at index `changed` (in `Grid::entries`
+/// order) is checked last, so a clash is reported against it.
+pub fn check_fit(
+    grid: &Grid,
+    across_words: &[ClueWord],
+    down_words: &[ClueWord],
+    changed: usize,
+) -> ApiResult<()> {
+    let mut letters: Vec<Vec<Option<char>>> = vec![vec![None; grid.width]; grid.height];
+    let mut answers: Vec<_> = grid
+        .entries()
+        .into_iter()
+        .zip(across_words.iter().chain(down_words))
+        .collect();
+    let last = answers.remove(changed);
+    answers.push(last);
+    for (entry, clue_word) in answers {
+        let word: Vec<char> = clue_word.word.to_uppercase().chars().collect();
+        if word.len() != entry.length {
+            return Err(ApiError::BadRequest(format!(
+                "{} has {} letters but {} {} needs {}",
+                clue_word.word,
+                word.len(),
+                entry.number,
+                direction_name(entry.direction),
+                entry.length
+            )));
+        }
+        for ((row, col), letter) in entry.cells().into_iter().zip(word) {
+            match letters[row][col] {
+                Some(existing) if existing != letter => {
+                    return Err(ApiError::BadRequest(format!(
+                        "{} doesn't fit {} {}: it crosses {} at row {}, column {}",
+                        clue_word.word,
+                        entry.number,
+                        direction_name(entry.direction),
+                        existing,
+                        row + 1,
+                        col + 1
+                    )));
+                }
+                _ => letters[row][col] = Some(letter),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct BanRequest {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BanResponse {
+    clue: ClueWord,
+    banned: bool,
+    /// Unpublished puzzles still using the clue, which a ban doesn't change.
+    unpublished_dates: Vec<String>,
+}
+
+fn unpublished_dates_using(repo: &Repo, clue_word_id: i64) -> ApiResult<Vec<String>> {
+    Ok(repo
+        .puzzles_using(clue_word_id)?
+        .into_iter()
+        .filter(|(_, status)| *status != PuzzleStatus::Published)
+        .map(|(date, _)| date)
+        .collect())
+}
+
+/// Keeps a clue out of future puzzles. Body: `{"reason": "..."}`, optional.
+async fn ban_clue(
+    _: Admin,
+    repo: web::Data<Repo>,
+    path: web::Path<i64>,
+    body: Option<web::Json<BanRequest>>,
+) -> ApiResult<HttpResponse> {
+    let id = path.into_inner();
+    let reason = body.and_then(|body| body.into_inner().reason);
+    let response = web::block(move || -> ApiResult<BanResponse> {
+        let clue = repo
+            .load_clue(id)?
+            .ok_or_else(|| ApiError::NotFound(format!("no clue with id {}", id)))?;
+        repo.ban_clue(id, reason.as_deref())?;
+        log::info!("Banned clue {} ({} \"{}\")", id, clue.word, clue.clue);
+        Ok(BanResponse {
+            clue,
+            banned: true,
+            unpublished_dates: unpublished_dates_using(&repo, id)?,
+        })
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+async fn unban_clue(
+    _: Admin,
+    repo: web::Data<Repo>,
+    path: web::Path<i64>,
+) -> ApiResult<HttpResponse> {
+    let id = path.into_inner();
+    let response = web::block(move || -> ApiResult<BanResponse> {
+        let clue = repo
+            .load_clue(id)?
+            .ok_or_else(|| ApiError::NotFound(format!("no clue with id {}", id)))?;
+        if !repo.unban_clue(id)? {
+            return Err(ApiError::NotFound(format!("clue {} isn't banned", id)));
+        }
+        log::info!("Lifted ban on clue {}", id);
+        Ok(BanResponse {
+            clue,
+            banned: false,
+            unpublished_dates: unpublished_dates_using(&repo, id)?,
+        })
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(response))
+}
@@ -0,0 +1,67 @@
+use crate::hints;
+use crate::models::{CheckRequest, CheckResponse, DailyPuzzle};
+
+/// Compares the player's guesses against the stored solution.
+///
+/// Cells missing from the request are treated as empty, and anything beyond
+/// the puzzle's dimensions is ignored. Blocks always come back as `None`.
+pub fn check_answers(puzzle: &DailyPuzzle, request: &CheckRequest) -> CheckResponse {
+    let grid = &puzzle.grid;
+    let mut solution: Vec<Vec<Option<char>>> = vec![vec![None; grid.width]; grid.height];
+    let words = puzzle.across_words.iter().chain(&puzzle.down_words);
+    for (entry, clue_word) in grid.entries().into_iter().zip(words) {
+        for ((row, col), letter) in entry.cells().into_iter().zip(clue_word.word.chars()) {
+            solution[row][col] = Some(letter);
+        }
+    }
+
+    let cells: Vec<Vec<Option<bool>>> = solution
+        .iter()
+        .enumerate()
+        .map(|(row, letters)| {
+            let guesses = request.grid.get(row).map(|r| r.as_slice()).unwrap_or(&[]);
+            letters
+                .iter()
+                .enumerate()
+                .map(|(col, letter)| letter.and_then(|letter| check_cell(letter, guesses.get(col))))
+                .collect()
+        })
+        .collect();
+
+    let plusword: Vec<Option<bool>> = puzzle
+        .plusword
+        .chars()
+        .enumerate()
+        .map(|(i, letter)| check_cell(letter, request.plusword.get(i)))
+        .collect();
+
+    let grid_solved = solution.iter().zip(&cells).all(|(letters, results)| {
+        letters
+            .iter()
+            .zip(results)
+            .all(|(letter, result)| letter.is_none() || *result == Some(true))
+    });
+    let solved = grid_solved && plusword.iter().all(|cell| *cell == Some(true));
+
+    let plusword_hints = plusword.iter().all(Option::is_some).then(|| {
+        let guess: String = request
+            .plusword
+            .iter()
+            .filter_map(|letter| letter.trim().chars().next())
+            .collect();
+        hints::score(&guess, &puzzle.plusword, 0)
+    });
+
+    CheckResponse {
+        cells,
+        plusword,
+        plusword_hints,
+        solved,
+    }
+}
+
+fn check_cell(expected: char, guess: Option<&String>) -> Option<bool> {
+    guess
+        .and_then(|guess| guess.trim().chars().next())
+        .map(|guess| guess.eq_ignore_ascii_case(&expected))
+}
@@ -0,0 +1,129 @@
+//! The `quinta` command line: one binary whose subcommands share the
+//! database path and server address.
+
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+
+use crate::{fetch_puzzles, import_puzzles, import_words};
+
+#[derive(Parser)]
+#[command(name = "quinta", version, about = "A daily Plusword-style crossword")]
+pub struct Cli {
+    #[command(flatten)]
+    pub settings: Settings,
+
+    /// What to do; runs the server if left out.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Settings shared by every subcommand. Each overrides the config file
+/// and environment; see `config`.
+#[derive(clap::Args)]
+pub struct Settings {
+    /// TOML config file [env: QUINTA_CONFIG] [default: quinta.toml, if present]
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// SQLite database file [env: QUINTA_DB] [default: quinta.db]
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+
+    /// Directory the game page is served from [env: QUINTA_STATIC_DIR] [default: static]
+    #[arg(long, global = true)]
+    pub static_dir: Option<PathBuf>,
+
+    /// Address the server listens on [env: QUINTA_BIND] [default: 127.0.0.1]
+    #[arg(long, global = true)]
+    pub bind: Option<String>,
+
+    /// Port the server listens on [env: QUINTA_PORT] [default: 8080]
+    #[arg(long, global = true)]
+    pub port: Option<u16>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the web server, keeping upcoming puzzles generated
+    Serve,
+    /// Bring the database schema up to date
+    Migrate,
+    /// Check the database's integrity and every stored puzzle
+    ValidateDb,
+    /// Print the settings in effect, secrets hidden
+    Config,
+    /// Generate and save the puzzle for a date
+    Generate {
+        #[arg(long)]
+        date: NaiveDate,
+        /// Replace a puzzle already stored for the date, unless published
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Print the puzzle stored for a date, answers included
+    Show {
+        #[arg(long)]
+        date: NaiveDate,
+        /// Print it as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate any missing puzzles for today and the next DAYS days
+    Pregenerate {
+        /// Defaults to generation.days_ahead, normally 7
+        days: Option<u64>,
+    },
+    /// Print a puzzle for a grid shape without saving it
+    Sample {
+        /// daily, mini, 6x6 or 7x7
+        #[arg(default_value = "daily")]
+        shape: String,
+        /// Random if left out
+        seed: Option<u64>,
+    },
+    /// Regenerate a date from its seed and compare it with what's stored
+    Regenerate {
+        date: NaiveDate,
+        /// Store the regenerated puzzle if none is stored
+        #[arg(long)]
+        save: bool,
+    },
+    /// Rate a clue from 1 (poor) to 5 (excellent)
+    RateClue {
+        clue_id: i64,
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=5))]
+        rating: u8,
+    },
+    /// List, add or remove blocklist patterns
+    Blocklist {
+        #[command(subcommand)]
+        action: Option<BlocklistAction>,
+    },
+    /// Report everything in the database that matches the blocklist
+    Audit,
+    /// Fetch Plusword puzzles into a JSON file
+    FetchPuzzles(fetch_puzzles::Args),
+    /// Import fetched puzzles into the database
+    ImportPuzzles(import_puzzles::Args),
+    /// Import a word list for pluswords
+    ImportWords(import_words::Args),
+}
+
+#[derive(Subcommand)]
+pub enum BlocklistAction {
+    /// List every pattern (the default)
+    List,
+    /// Block a word, or with --substring any text containing it
+    Add {
+        pattern: String,
+        /// Match anywhere in the text, even inside a longer word
+        #[arg(long)]
+        substring: bool,
+        #[arg(long, default_value = "offensive")]
+        category: String,
+    },
+    /// Unblock a pattern
+    Remove { pattern: String },
+}
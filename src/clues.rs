@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::{self, MAX_RATING, MIN_RATING};
+use crate::freshness::PastPuzzle;
+use crate::models::ClueWord;
+
+/// Editorial rating assumed for clues nobody has rated.
+const DEFAULT_RATING: u8 = 3;
+
+/// A clue last used this many days ago is as good as one never used.
+const FORGOTTEN_AFTER_DAYS: i64 = 730;
+
+/// Which kind of clue to prefer when a word has several.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClueStyle {
+    #[default]
+    Any,
+    Straight,
+    Cryptic,
+}
+
+impl FromStr for ClueStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" => Ok(ClueStyle::Any),
+            "straight" => Ok(ClueStyle::Straight),
+            "cryptic" => Ok(ClueStyle::Cryptic),
+            other => Err(format!(
+                "unknown clue style '{}' (expected any, straight or cryptic)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ClueStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ClueStyle::Any => "any",
+            ClueStyle::Straight => "straight",
+            ClueStyle::Cryptic => "cryptic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a puzzle's clues were picked. Stored with the puzzle, so it can be
+/// regenerated with the same choices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClueStrategy {
+    pub style: ClueStyle,
+    /// Difficulty rating (see `difficulty`) the clues should suit, if any.
+    pub target_difficulty: Option<f64>,
+}
+
+impl fmt::Display for ClueStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} clues", self.style)?;
+        if let Some(target) = self.target_difficulty {
+            write!(f, " for difficulty {:.1}", target)?;
+        }
+        Ok(())
+    }
+}
+
+/// Picks one clue for each answer in a fill.
+///
+/// Every candidate gets a penalty made up of: the wrong style for the
+/// strategy, a poor editorial rating, trickiness far from what the target
+/// difficulty calls for, and having been used recently. The lowest penalty
+/// wins; ties go to whichever candidate came first.
+pub struct ClueSelector {
+    strategy: ClueStrategy,
+    /// Most recent date each clue appeared before the puzzle being built.
+    last_used: HashMap<i64, NaiveDate>,
+    ratings: HashMap<i64, u8>,
+    date: NaiveDate,
+}
+
+impl ClueSelector {
+    /// A selector for a puzzle on `date`. Only puzzles dated before it
+    /// count towards how recently a clue was used, like the freshness rules.
+    pub fn new(
+        strategy: ClueStrategy,
+        date: NaiveDate,
+        history: &[PastPuzzle],
+        ratings: HashMap<i64, u8>,
+    ) -> Self {
+        let mut last_used: HashMap<i64, NaiveDate> = HashMap::new();
+        for past in history.iter().filter(|past| past.date < date) {
+            for &id in &past.clue_word_ids {
+                let used = last_used.entry(id).or_insert(past.date);
+                *used = (*used).max(past.date);
+            }
+        }
+
+        ClueSelector {
+            strategy,
+            last_used,
+            ratings,
+            date,
+        }
+    }
+
+    pub fn strategy(&self) -> ClueStrategy {
+        self.strategy
+    }
+
+    /// The best of `candidates`, all clues for the same word. `None` only if
+    /// there are no candidates.
+    pub fn choose<'a>(&self, candidates: &[&'a ClueWord]) -> Option<&'a ClueWord> {
+        let mut best: Option<(f64, &ClueWord)> = None;
+        for &candidate in candidates {
+            let penalty = self.penalty(candidate);
+            if best.is_none_or(|(best_penalty, _)| penalty < best_penalty) {
+                best = Some((penalty, candidate));
+            }
+        }
+        best.map(|(_, clue_word)| clue_word)
+    }
+
+    fn penalty(&self, clue_word: &ClueWord) -> f64 {
+        let cryptic = difficulty::is_cryptic(&clue_word.clue);
+        let style = match self.strategy.style {
+            ClueStyle::Any => 0.0,
+            ClueStyle::Straight if cryptic => 1.0,
+            ClueStyle::Cryptic if !cryptic => 1.0,
+            _ => 0.0,
+        };
+
+        let rating = self
+            .ratings
+            .get(&clue_word.id)
+            .copied()
+            .unwrap_or(DEFAULT_RATING);
+        let quality = f64::from(5 - rating.clamp(1, 5)) / 4.0;
+
+        let fit = match self.strategy.target_difficulty {
+            Some(target) => {
+                let wanted = (target - MIN_RATING) / (MAX_RATING - MIN_RATING);
+                (difficulty::clue_trickiness(&clue_word.clue) - wanted).abs()
+            }
+            None => 0.0,
+        };
+
+        let staleness = match self.last_used.get(&clue_word.id) {
+            Some(&used) => {
+                let days = (self.date - used).num_days().clamp(0, FORGOTTEN_AFTER_DAYS);
+                1.0 - days as f64 / FORGOTTEN_AFTER_DAYS as f64
+            }
+            None => 0.0,
+        };
+
+        style + 0.5 * quality + 0.5 * fit + 0.5 * staleness
+    }
+}
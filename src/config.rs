@@ -0,0 +1,361 @@
+//! Settings for the server and the generator. Each comes from, in order of
+//! precedence: a command-line flag, a `QUINTA_*` environment variable, the
+//! TOML config file, or its default. Everything is checked once at
+//! startup, so a typo stops the program instead of being quietly ignored.
+
+use std::env::{self, VarError};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Settings;
+use crate::clues::ClueStyle;
+use crate::error::{self, Error};
+use crate::generation::DEFAULT_DIFFICULTY_CANDIDATES;
+use crate::{db, freshness, generator, scheduler};
+
+/// Config file read if it exists and neither `--config` nor
+/// `QUINTA_CONFIG` names another.
+pub const DEFAULT_PATH: &str = "quinta.toml";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub puzzle: PuzzleConfig,
+    pub generation: GenerationConfig,
+    pub moderation: ModerationConfig,
+    pub admin: AdminConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address the server listens on.
+    pub bind: String,
+    pub port: u16,
+    /// Directory the game page is served from.
+    pub static_dir: PathBuf,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "127.0.0.1".to_owned(),
+            port: 8080,
+            static_dir: PathBuf::from("static"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// SQLite database file.
+    pub path: PathBuf,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            path: PathBuf::from(db::DEFAULT_PATH),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PuzzleConfig {
+    /// IANA zone whose midnight rolls over to the next puzzle, such as
+    /// `Europe/London`. The server's local time if left out.
+    pub timezone: Option<Tz>,
+    /// Serve each player the puzzle for their own local date, as long as
+    /// it's within a day of the canonical date.
+    pub client_local: bool,
+}
+
+impl PuzzleConfig {
+    /// The current puzzle date in the canonical timezone.
+    pub fn today(&self) -> NaiveDate {
+        match self.timezone {
+            Some(tz) => chrono::Utc::now().with_timezone(&tz).date_naive(),
+            None => chrono::Local::now().date_naive(),
+        }
+    }
+
+    /// The latest date anyone may play: today, or in client-local mode
+    /// tomorrow, which it already is in zones ahead of ours.
+    pub fn latest(&self) -> NaiveDate {
+        let today = self.today();
+        match self.client_local {
+            true => today.succ_opt().unwrap_or(today),
+            false => today,
+        }
+    }
+
+    /// The date to serve as "today" to a client whose local date is
+    /// `client_date`. Without client-local mode, or without a client date,
+    /// it's the canonical date.
+    pub fn today_for(&self, client_date: Option<NaiveDate>) -> Result<NaiveDate, String> {
+        let today = self.today();
+        match client_date {
+            Some(date) if self.client_local => {
+                if (date - today).num_days().abs() > 1 {
+                    return Err(format!(
+                        "{} is more than a day away from today ({})",
+                        date, today
+                    ));
+                }
+                Ok(date)
+            }
+            _ => Ok(today),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerationConfig {
+    /// How many days beyond today the scheduler keeps generated.
+    pub days_ahead: u64,
+    /// An answer can't appear within this many days of a puzzle using it.
+    pub word_fresh_days: u32,
+    /// Clue text can't appear within this many days of a puzzle using it.
+    pub clue_fresh_days: u32,
+    pub clue_style: ClueStyle,
+    /// Candidate puzzles built per date when aiming for its difficulty.
+    pub difficulty_candidates: u32,
+    /// How many answers a plusword's hints may leave open.
+    pub max_plusword_candidates: usize,
+    /// Mixed into every date's seed; see `generator::seed_for_date`.
+    pub seed_secret: String,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        let freshness = freshness::Policy::default();
+        GenerationConfig {
+            days_ahead: scheduler::DEFAULT_DAYS_AHEAD,
+            word_fresh_days: freshness.word_days as u32,
+            clue_fresh_days: freshness.clue_days as u32,
+            clue_style: ClueStyle::default(),
+            difficulty_candidates: DEFAULT_DIFFICULTY_CANDIDATES,
+            max_plusword_candidates: generator::DEFAULT_MAX_PLUSWORD_CANDIDATES,
+            seed_secret: String::new(),
+        }
+    }
+}
+
+impl GenerationConfig {
+    pub fn freshness(&self) -> freshness::Policy {
+        freshness::Policy {
+            word_days: self.word_fresh_days.into(),
+            clue_days: self.clue_fresh_days.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    /// Blocklist categories to filter on; every category if left out.
+    pub blocklist_categories: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// Token for the admin API. Without one the admin API is off and
+    /// puzzles skip review.
+    pub token: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// off, error, warn, info, debug or trace; info by default. At info
+    /// startup, every request and every puzzle generated is logged; debug
+    /// adds how generation went.
+    pub level: LevelFilter,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: LevelFilter::Info,
+        }
+    }
+}
+
+/// Reads `name` from the environment, if it's set.
+fn var<T>(name: &str) -> error::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|e| Error::Config(format!("{}={:?}: {}", name, value, e))),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(e) => Err(Error::Config(format!("{}: {}", name, e))),
+    }
+}
+
+/// A comma-separated list of blocklist categories, lower-cased.
+fn parse_categories(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|category| category.trim().to_lowercase())
+        .filter(|category| !category.is_empty())
+        .collect()
+}
+
+impl Config {
+    /// Works out the settings in effect for `settings`, the command-line
+    /// flags, and checks them.
+    pub fn load(settings: &Settings) -> error::Result<Self> {
+        let explicit = match &settings.config {
+            Some(path) => Some(path.clone()),
+            None => var::<PathBuf>("QUINTA_CONFIG")?,
+        };
+        let mut config = match explicit {
+            Some(path) => Config::read(&path)?,
+            None if Path::new(DEFAULT_PATH).exists() => Config::read(Path::new(DEFAULT_PATH))?,
+            None => Config::default(),
+        };
+        config.apply_env()?;
+        config.apply_flags(settings);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> error::Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::Io(format!("reading {}", path.display()), e))?;
+        toml::from_str(&content).map_err(|e| Error::Config(format!("in {}: {}", path.display(), e)))
+    }
+
+    fn apply_env(&mut self) -> error::Result<()> {
+        fn set<T>(field: &mut T, name: &str) -> error::Result<()>
+        where
+            T: FromStr,
+            T::Err: Display,
+        {
+            if let Some(value) = var(name)? {
+                *field = value;
+            }
+            Ok(())
+        }
+
+        set(&mut self.server.bind, "QUINTA_BIND")?;
+        set(&mut self.server.port, "QUINTA_PORT")?;
+        set(&mut self.server.static_dir, "QUINTA_STATIC_DIR")?;
+        set(&mut self.database.path, "QUINTA_DB")?;
+        if let Some(timezone) = var("QUINTA_TIMEZONE")? {
+            self.puzzle.timezone = Some(timezone);
+        }
+        set(&mut self.puzzle.client_local, "QUINTA_CLIENT_LOCAL")?;
+        let generation = &mut self.generation;
+        set(&mut generation.days_ahead, "QUINTA_DAYS_AHEAD")?;
+        set(&mut generation.word_fresh_days, "QUINTA_WORD_FRESH_DAYS")?;
+        set(&mut generation.clue_fresh_days, "QUINTA_CLUE_FRESH_DAYS")?;
+        set(&mut generation.clue_style, "QUINTA_CLUE_STYLE")?;
+        set(
+            &mut generation.difficulty_candidates,
+            "QUINTA_DIFFICULTY_CANDIDATES",
+        )?;
+        set(
+            &mut generation.max_plusword_candidates,
+            "QUINTA_MAX_PLUSWORD_CANDIDATES",
+        )?;
+        set(&mut generation.seed_secret, "QUINTA_SEED_SECRET")?;
+        if let Some(categories) = var::<String>("QUINTA_BLOCKLIST_CATEGORIES")? {
+            self.moderation.blocklist_categories = Some(parse_categories(&categories));
+        }
+        // An empty token turns the admin API off, as if it weren't set.
+        if let Some(token) = var::<String>("QUINTA_ADMIN_TOKEN")? {
+            self.admin.token = Some(token).filter(|token| !token.is_empty());
+        }
+        set(&mut self.log.level, "QUINTA_LOG")?;
+        Ok(())
+    }
+
+    fn apply_flags(&mut self, settings: &Settings) {
+        if let Some(bind) = &settings.bind {
+            self.server.bind = bind.clone();
+        }
+        if let Some(port) = settings.port {
+            self.server.port = port;
+        }
+        if let Some(static_dir) = &settings.static_dir {
+            self.server.static_dir = static_dir.clone();
+        }
+        if let Some(db) = &settings.db {
+            self.database.path = db.clone();
+        }
+    }
+
+    fn validate(&mut self) -> error::Result<()> {
+        let invalid = |message: &str| Err(Error::Config(message.to_owned()));
+
+        if self.server.bind.trim().is_empty() {
+            return invalid("server.bind can't be empty");
+        }
+        if self.database.path.as_os_str().is_empty() {
+            return invalid("database.path can't be empty");
+        }
+        if self.generation.difficulty_candidates < 1 {
+            return invalid("generation.difficulty_candidates must be at least 1");
+        }
+        if self.generation.max_plusword_candidates < 1 {
+            return invalid("generation.max_plusword_candidates must be at least 1");
+        }
+        if self.admin.token.as_ref().is_some_and(|t| t.is_empty()) {
+            return invalid("admin.token can't be empty; leave it out to turn the admin API off");
+        }
+        if let Some(categories) = &mut self.moderation.blocklist_categories {
+            *categories = parse_categories(&categories.join(","));
+        }
+        Ok(())
+    }
+
+    /// The settings as TOML, with secrets hidden.
+    pub fn to_redacted_toml(&self) -> error::Result<String> {
+        let mut shown = self.clone();
+        let hide = |secret: &mut String| {
+            if !secret.is_empty() {
+                *secret = "(hidden)".to_owned();
+            }
+        };
+        hide(&mut shown.generation.seed_secret);
+        if let Some(token) = &mut shown.admin.token {
+            hide(token);
+        }
+        toml::to_string_pretty(&shown).map_err(|e| Error::Config(e.to_string()))
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Makes `config` the one `get` returns. Only the first call counts.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// The settings in effect; `main` loads them before anything else runs.
+pub fn get() -> &'static Config {
+    CONFIG
+        .get()
+        .expect("configuration used before it was loaded")
+}
@@ -0,0 +1,766 @@
+use chrono::NaiveDate;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{OptionalExtension, Transaction};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::freshness::PastPuzzle;
+use crate::grid::{Direction, Grid};
+use crate::migrations;
+use crate::models::{ClueWord, Puzzle, PuzzleList, PuzzleStatus, PuzzleSummary};
+use crate::moderation::{BlockedPattern, Blocklist, MatchKind};
+
+pub const DEFAULT_PATH: &str = "quinta.db";
+
+/// Most connections the pool keeps open. SQLite only allows one writer at a
+/// time anyway; this is for concurrent readers.
+const POOL_SIZE: u32 = 8;
+
+type Pool = r2d2::Pool<SqliteConnectionManager>;
+type PooledConnection = r2d2::PooledConnection<SqliteConnectionManager>;
+
+/// All database access for the server. Cheap to clone; clones share one
+/// connection pool.
+///
+/// Every method blocks, so call them from `web::block` (or another
+/// blocking context), never directly on an async worker.
+#[derive(Clone)]
+pub struct Repo {
+    pool: Pool,
+}
+
+impl Repo {
+    /// Opens a pool of connections to the database at `path`, in WAL mode so
+    /// readers aren't held up while a puzzle is being saved.
+    pub fn open(path: &Path) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+            conn.execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA busy_timeout = 5000;
+                 PRAGMA foreign_keys = ON;",
+            )
+        });
+        let pool = r2d2::Pool::builder().max_size(POOL_SIZE).build(manager)?;
+        Ok(Repo { pool })
+    }
+
+    fn conn(&self) -> Result<PooledConnection> {
+        self.pool.get().map_err(Error::from)
+    }
+
+    /// Every clue-word pair generation may use, i.e. all but the banned
+    /// ones, unless `include_banned`.
+    pub fn load_all_clues(&self, include_banned: bool) -> Result<Vec<ClueWord>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, clue, word FROM clue_word_pairs
+                 WHERE ?1 OR id NOT IN (SELECT clue_word_id FROM clue_bans)
+                 ORDER BY id",
+        )?;
+
+        stmt.query_map([include_banned], |row| {
+            let id: i64 = row.get(0)?;
+            let clue: String = row.get(1)?;
+            let word: String = row.get(2)?;
+            Ok(ClueWord { id, word, clue })
+        })
+        .and_then(|e| e.collect())
+        .map_err(Error::from)
+    }
+
+    pub fn load_words(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached("SELECT word FROM word ORDER BY id")?;
+
+        stmt.query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(Error::from)
+    }
+
+    /// Each word's commonness, for the words a word list gave a frequency
+    /// for, keyed by upper-cased word.
+    pub fn load_word_commonness(&self) -> Result<HashMap<String, f64>> {
+        let conn = self.conn()?;
+
+        let mut stmt =
+            conn.prepare_cached("SELECT word, commonness FROM word WHERE commonness IS NOT NULL")?;
+
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?.to_uppercase(), row.get(1)?))
+        })
+        .and_then(|rows| rows.collect())
+        .map_err(Error::from)
+    }
+
+    /// The puzzle stored for `date`, with its number in the series. Fails if
+    /// the stored answers don't cover every entry in its grid.
+    pub fn load_puzzle(&self, date: &str) -> Result<Option<(Puzzle, i64)>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, plusword,
+                        (SELECT COUNT(*) FROM puzzles earlier WHERE earlier.date <= puzzles.date),
+                        grid, seed, difficulty, clue_strategy, status
+                 FROM puzzles where date = ?1",
+        )?;
+
+        let row = stmt
+            .query_row([date], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    // Stored as the signed bit pattern, since SQLite integers are i64.
+                    row.get::<_, Option<i64>>(4)?,
+                    row.get::<_, Option<f64>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, String>(7)?,
+                ))
+            })
+            .optional()?;
+
+        let Some((puzzle_id, plusword, number, grid_json, seed, difficulty, strategy_json, status)) =
+            row
+        else {
+            return Ok(None);
+        };
+        let status = PuzzleStatus::parse(&status)
+            .ok_or_else(|| Error::Data(format!("bad status {} for {}", status, date)))?;
+
+        // Puzzles from before grid shapes were stored are all the daily 5x5.
+        let grid: Grid = match grid_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| Error::Data(format!("bad grid for {}: {}", date, e)))?,
+            None => Grid::daily(),
+        };
+        let clue_strategy = strategy_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| Error::Data(format!("bad clue strategy for {}: {}", date, e)))?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT entries.direction, entries.position, pairs.id, pairs.word, pairs.clue
+                 FROM puzzle_entries AS entries
+                 JOIN clue_word_pairs AS pairs ON pairs.id = entries.clue_word_id
+                 WHERE entries.puzzle_id = ?1
+                 ORDER BY entries.direction, entries.position",
+        )?;
+        let rows = stmt
+            .query_map([puzzle_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, usize>(1)?,
+                    ClueWord {
+                        id: row.get(2)?,
+                        word: row.get(3)?,
+                        clue: row.get(4)?,
+                    },
+                ))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())?;
+
+        let mut across_words = Vec::new();
+        let mut down_words = Vec::new();
+        for (direction, position, clue_word) in rows {
+            let words = match direction.as_str() {
+                "across" => &mut across_words,
+                _ => &mut down_words,
+            };
+            if position != words.len() {
+                return Err(Error::Data(format!(
+                    "puzzle for {} has no answer for {} entry {}",
+                    date,
+                    direction,
+                    words.len()
+                )));
+            }
+            words.push(clue_word);
+        }
+
+        let entries = grid.entries();
+        let across_count = entries
+            .iter()
+            .filter(|entry| entry.direction == Direction::Across)
+            .count();
+        let down_count = entries.len() - across_count;
+        if across_words.len() != across_count || down_words.len() != down_count {
+            return Err(Error::Data(format!(
+                "puzzle for {} is incomplete: its grid has {} across and {} down entries, \
+                 but {} across and {} down answers are stored",
+                date,
+                across_count,
+                down_count,
+                across_words.len(),
+                down_words.len()
+            )));
+        }
+
+        log::debug!("Loaded existing puzzle for {}", date);
+        let puzzle = Puzzle {
+            date: date.to_owned(),
+            grid,
+            seed: seed.map(|seed| seed as u64),
+            across_words,
+            down_words,
+            plusword,
+            difficulty,
+            clue_strategy,
+            status,
+        };
+        Ok(Some((puzzle, number)))
+    }
+
+    /// Lists puzzle dates up to and including `until`, newest first,
+    /// leaving out drafts. Numbers still count drafts, so they match
+    /// `load_puzzle`.
+    pub fn list_puzzles(&self, until: &str, page: u32, per_page: u32) -> Result<PuzzleList> {
+        let conn = self.conn()?;
+
+        let total: i64 = conn
+            .prepare_cached("SELECT COUNT(*) FROM puzzles WHERE date <= ?1 AND status != 'draft'")
+            .and_then(|mut stmt| stmt.query_row([until], |row| row.get(0)))?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT date, number FROM (
+                     SELECT date, status, ROW_NUMBER() OVER (ORDER BY date) AS number
+                     FROM puzzles WHERE date <= ?1
+                 )
+                 WHERE status != 'draft'
+                 ORDER BY date DESC
+                 LIMIT ?2 OFFSET ?3",
+        )?;
+
+        let offset = i64::from(page.saturating_sub(1)) * i64::from(per_page);
+        let puzzles = stmt
+            .query_map((until, per_page, offset), |row| {
+                Ok(PuzzleSummary {
+                    date: row.get(0)?,
+                    number: row.get(1)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())?;
+
+        Ok(PuzzleList {
+            page,
+            per_page,
+            total,
+            puzzles,
+        })
+    }
+
+    /// Saves a generated puzzle, recording exactly the clue-word pair chosen
+    /// for each entry. Returns `false` without touching the existing row if a
+    /// puzzle for that date is already stored.
+    pub fn save_puzzle(&self, puzzle: &Puzzle) -> Result<bool> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        if !insert_puzzle(&tx, puzzle)? {
+            log::info!("Puzzle for {} already exists, keeping it", puzzle.date);
+            return Ok(false);
+        }
+
+        tx.commit()?;
+        log::info!("Saved puzzle for {}", puzzle.date);
+
+        Ok(true)
+    }
+
+    /// Saves a generated puzzle in place of whatever is stored for its date,
+    /// unless that has already been published. Returns `false`, changing
+    /// nothing, if it has.
+    pub fn replace_puzzle(&self, puzzle: &Puzzle) -> Result<bool> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        tx.prepare_cached("DELETE FROM puzzles WHERE date = ?1 AND status != 'published'")
+            .and_then(|mut stmt| stmt.execute([&puzzle.date]))?;
+        if !insert_puzzle(&tx, puzzle)? {
+            return Ok(false);
+        }
+
+        tx.commit()?;
+        log::info!("Replaced puzzle for {}", puzzle.date);
+
+        Ok(true)
+    }
+
+    /// Dates from `from` onwards that have a puzzle, earliest first.
+    pub fn upcoming_dates(&self, from: &str) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+
+        let mut stmt =
+            conn.prepare_cached("SELECT date FROM puzzles WHERE date >= ?1 ORDER BY date")?;
+
+        stmt.query_map([from], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(Error::from)
+    }
+
+    /// Moves the puzzle for `date` to `status` if it is currently in one of
+    /// `from`. Returns whether it was.
+    pub fn set_status(
+        &self,
+        date: &str,
+        from: &[PuzzleStatus],
+        status: PuzzleStatus,
+    ) -> Result<bool> {
+        let conn = self.conn()?;
+
+        let mut updated = 0;
+        for current in from {
+            updated += conn
+                .prepare_cached("UPDATE puzzles SET status = ?1 WHERE date = ?2 AND status = ?3")
+                .and_then(|mut stmt| stmt.execute((status.as_str(), date, current.as_str())))?;
+        }
+        Ok(updated > 0)
+    }
+
+    /// Puts `clue_word_id` in one entry of the unpublished puzzle for
+    /// `date`, with its difficulty re-rated. The seed and clue strategy are
+    /// cleared, since they no longer produce this puzzle. Returns `false` if
+    /// there is no such entry in an unpublished puzzle.
+    pub fn replace_entry(
+        &self,
+        date: &str,
+        direction: Direction,
+        position: usize,
+        clue_word_id: i64,
+        difficulty: f64,
+    ) -> Result<bool> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        let direction = match direction {
+            Direction::Across => "across",
+            Direction::Down => "down",
+        };
+        let updated = tx
+            .prepare_cached(
+                "UPDATE puzzle_entries SET clue_word_id = ?1
+                 WHERE direction = ?2 AND position = ?3 AND puzzle_id =
+                     (SELECT id FROM puzzles WHERE date = ?4 AND status != 'published')",
+            )
+            .and_then(|mut stmt| stmt.execute((clue_word_id, direction, position, date)))?;
+        if updated == 0 {
+            return Ok(false);
+        }
+
+        tx.prepare_cached(
+            "UPDATE puzzles SET difficulty = ?1, seed = NULL, clue_strategy = NULL
+             WHERE date = ?2",
+        )
+        .and_then(|mut stmt| stmt.execute((difficulty, date)))?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// The clue-word pair with this id, banned or not.
+    pub fn load_clue(&self, clue_word_id: i64) -> Result<Option<ClueWord>> {
+        let conn = self.conn()?;
+
+        conn.prepare_cached("SELECT id, word, clue FROM clue_word_pairs WHERE id = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_row([clue_word_id], |row| {
+                    Ok(ClueWord {
+                        id: row.get(0)?,
+                        word: row.get(1)?,
+                        clue: row.get(2)?,
+                    })
+                })
+                .optional()
+            })
+            .map_err(Error::from)
+    }
+
+    pub fn is_banned(&self, clue_word_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+
+        conn.prepare_cached("SELECT COUNT(*) > 0 FROM clue_bans WHERE clue_word_id = ?1")
+            .and_then(|mut stmt| stmt.query_row([clue_word_id], |row| row.get(0)))
+            .map_err(Error::from)
+    }
+
+    /// Keeps a clue-word pair out of future puzzles, replacing the reason
+    /// if it's already banned. Puzzles already using it are left alone.
+    pub fn ban_clue(&self, clue_word_id: i64, reason: Option<&str>) -> Result<()> {
+        let conn = self.conn()?;
+
+        conn.prepare_cached(
+            "INSERT INTO clue_bans (clue_word_id, reason) VALUES (?1, ?2)
+             ON CONFLICT(clue_word_id) DO UPDATE SET reason = excluded.reason",
+        )
+        .and_then(|mut stmt| stmt.execute((clue_word_id, reason)))?;
+        Ok(())
+    }
+
+    /// Lifts a ban. Returns `false` if the clue wasn't banned.
+    pub fn unban_clue(&self, clue_word_id: i64) -> Result<bool> {
+        let conn = self.conn()?;
+
+        conn.prepare_cached("DELETE FROM clue_bans WHERE clue_word_id = ?1")
+            .and_then(|mut stmt| stmt.execute([clue_word_id]))
+            .map(|deleted| deleted > 0)
+            .map_err(Error::from)
+    }
+
+    /// Dates and statuses of the puzzles that use `clue_word_id`, earliest
+    /// first.
+    pub fn puzzles_using(&self, clue_word_id: i64) -> Result<Vec<(String, PuzzleStatus)>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT DISTINCT puzzles.date, puzzles.status
+                 FROM puzzles
+                 JOIN puzzle_entries AS entries ON entries.puzzle_id = puzzles.id
+                 WHERE entries.clue_word_id = ?1
+                 ORDER BY puzzles.date",
+        )?;
+
+        let rows = stmt
+            .query_map([clue_word_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())?;
+
+        rows.into_iter()
+            .map(|(date, status)| {
+                let status = PuzzleStatus::parse(&status)
+                    .ok_or_else(|| Error::Data(format!("bad status {} for {}", status, date)))?;
+                Ok((date, status))
+            })
+            .collect()
+    }
+
+    /// The blocklist, limited to `categories` if given.
+    pub fn load_blocklist(&self, categories: Option<&[String]>) -> Result<Blocklist> {
+        let conn = self.conn()?;
+        Blocklist::load(&conn, categories).map_err(Error::from)
+    }
+
+    /// Every blocklist pattern, oldest first.
+    pub fn list_blocklist(&self) -> Result<Vec<BlockedPattern>> {
+        let conn = self.conn()?;
+
+        let mut stmt =
+            conn.prepare_cached("SELECT pattern, kind, category FROM blocklist ORDER BY id")?;
+
+        stmt.query_map([], |row| {
+            let kind: String = row.get(1)?;
+            Ok(BlockedPattern {
+                pattern: row.get(0)?,
+                kind: MatchKind::parse(&kind),
+                category: row.get(2)?,
+            })
+        })
+        .and_then(|rows| rows.collect())
+        .map_err(Error::from)
+    }
+
+    /// Adds a pattern to the blocklist, or moves it to `category` if it's
+    /// already there.
+    pub fn add_blocklist_pattern(&self, blocked: &BlockedPattern) -> Result<()> {
+        let conn = self.conn()?;
+
+        conn.prepare_cached(
+            "INSERT INTO blocklist (pattern, kind, category) VALUES (?1, ?2, ?3)
+             ON CONFLICT(pattern, kind) DO UPDATE SET category = excluded.category",
+        )
+        .and_then(|mut stmt| {
+            stmt.execute((&blocked.pattern, blocked.kind.as_str(), &blocked.category))
+        })?;
+        Ok(())
+    }
+
+    /// Removes a pattern, of either kind, from the blocklist. Returns how
+    /// many rows went.
+    pub fn remove_blocklist_pattern(&self, pattern: &str) -> Result<usize> {
+        let conn = self.conn()?;
+
+        conn.prepare_cached("DELETE FROM blocklist WHERE pattern = ?1")
+            .and_then(|mut stmt| stmt.execute([pattern]))
+            .map_err(Error::from)
+    }
+
+    /// Editorial ratings from 1 (poor) to 5 (excellent), by clue-word pair
+    /// id. Clues nobody has rated are missing.
+    pub fn load_clue_ratings(&self) -> Result<HashMap<i64, u8>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached("SELECT clue_word_id, rating FROM clue_ratings")?;
+
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .and_then(|rows| rows.collect())
+            .map_err(Error::from)
+    }
+
+    /// Rates a clue-word pair from 1 to 5, replacing any earlier rating.
+    /// Returns the rated clue, or `None` if there is no pair with that id.
+    pub fn set_clue_rating(&self, clue_word_id: i64, rating: u8) -> Result<Option<ClueWord>> {
+        let clue_word = self.load_clue(clue_word_id)?;
+        if clue_word.is_none() {
+            return Ok(None);
+        }
+
+        let conn = self.conn()?;
+        conn.prepare_cached(
+            "INSERT INTO clue_ratings (clue_word_id, rating) VALUES (?1, ?2)
+             ON CONFLICT(clue_word_id) DO UPDATE SET
+                 rating = excluded.rating, updated_at = CURRENT_TIMESTAMP",
+        )
+        .and_then(|mut stmt| stmt.execute((clue_word_id, rating)))?;
+        Ok(clue_word)
+    }
+
+    /// Reads every stored puzzle's answers and plusword, for the freshness
+    /// rules.
+    pub fn load_history(&self) -> Result<Vec<PastPuzzle>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT puzzles.date, puzzles.plusword, entries.clue_word_id
+                 FROM puzzles
+                 LEFT JOIN puzzle_entries AS entries ON entries.puzzle_id = puzzles.id
+                 ORDER BY puzzles.date",
+        )?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                ))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())?;
+
+        let mut history: Vec<PastPuzzle> = Vec::new();
+        let mut last_date: Option<String> = None;
+        for (date, plusword, clue_word_id) in rows {
+            if last_date.as_ref() != Some(&date) {
+                let parsed = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|e| Error::Data(format!("bad date {} in puzzles: {}", date, e)))?;
+                history.push(PastPuzzle {
+                    date: parsed,
+                    clue_word_ids: Vec::new(),
+                    plusword,
+                });
+                last_date = Some(date);
+            }
+            if let (Some(past), Some(id)) = (history.last_mut(), clue_word_id) {
+                past.clue_word_ids.push(id);
+            }
+        }
+        Ok(history)
+    }
+
+    /// Every date with a stored puzzle, whatever its status, oldest first.
+    pub fn puzzle_dates(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare_cached("SELECT date FROM puzzles ORDER BY date")?;
+
+        stmt.query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(Error::from)
+    }
+
+    /// What SQLite itself finds wrong: corruption, from `integrity_check`,
+    /// and rows referring to missing ones, from `foreign_key_check`.
+    pub fn integrity_problems(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+
+        let mut problems: Vec<String> = conn
+            .prepare("PRAGMA integrity_check")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })?
+            .into_iter()
+            .filter(|message| message != "ok")
+            .collect();
+
+        let dangling = conn
+            .prepare("PRAGMA foreign_key_check")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok(format!(
+                        "{} row {} refers to a missing {} row",
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<i64>>(1)?.unwrap_or_default(),
+                        row.get::<_, String>(2)?
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            })?;
+        problems.extend(dangling);
+        Ok(problems)
+    }
+
+    /// Applies any pending schema migrations, with `today` as the puzzle
+    /// date they backfill against. Returns the versions applied.
+    pub fn migrate(&self, today: NaiveDate) -> Result<Vec<u32>> {
+        let mut conn = self.conn()?;
+        migrations::migrate(&mut conn, today).map_err(Error::from)
+    }
+
+    /// Fails unless the database has had every migration, so the server
+    /// never runs queries against tables it doesn't understand.
+    pub fn check_schema(&self) -> Result<()> {
+        let conn = self.conn()?;
+        let current = migrations::current_version(&conn)?;
+        let pending = migrations::pending(&conn)?;
+        match pending.last() {
+            None => Ok(()),
+            Some(latest) => Err(Error::Schema(format!(
+                "database schema is at version {} but {} is needed; run `quinta migrate` first",
+                current, latest.version
+            ))),
+        }
+    }
+}
+
+/// Inserts `puzzle` and its entries. Returns `false`, inserting nothing, if
+/// a puzzle for that date is already stored.
+fn insert_puzzle(tx: &Transaction, puzzle: &Puzzle) -> Result<bool> {
+    let grid_json = serde_json::to_string(&puzzle.grid).map_err(|e| Error::Data(e.to_string()))?;
+    let seed = puzzle.seed.map(|seed| seed as i64);
+    let strategy_json = puzzle
+        .clue_strategy
+        .map(|strategy| serde_json::to_string(&strategy))
+        .transpose()
+        .map_err(|e| Error::Data(e.to_string()))?;
+
+    let inserted = tx
+        .prepare_cached(
+            "INSERT INTO puzzles (date, plusword, grid, seed, difficulty, clue_strategy, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(date) DO NOTHING",
+        )
+        .and_then(|mut stmt| {
+            stmt.execute((
+                &puzzle.date,
+                &puzzle.plusword,
+                &grid_json,
+                seed,
+                puzzle.difficulty,
+                strategy_json,
+                puzzle.status.as_str(),
+            ))
+        })?;
+
+    if inserted == 0 {
+        return Ok(false);
+    }
+    let puzzle_id = tx.last_insert_rowid();
+
+    for (direction, words) in [
+        ("across", &puzzle.across_words),
+        ("down", &puzzle.down_words),
+    ] {
+        for (position, clue_word) in words.iter().enumerate() {
+            tx.prepare_cached(
+                "INSERT INTO puzzle_entries (puzzle_id, direction, position, clue_word_id)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )
+            .and_then(|mut stmt| stmt.execute((puzzle_id, direction, position, clue_word.id)))?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clues::{ClueStrategy, ClueStyle};
+    use std::path::PathBuf;
+
+    /// A database file in the temp directory, deleted when dropped.
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("quinta-{}-{}.db", name, std::process::id()));
+            let db = TempDb(path);
+            db.remove();
+            db
+        }
+
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut path = self.0.clone().into_os_string();
+                path.push(suffix);
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    fn add_clue(repo: &Repo, word: &str, clue: &str) -> ClueWord {
+        let conn = repo.conn().unwrap();
+        conn.execute(
+            "INSERT INTO clue_word_pairs (word, clue, slug) VALUES (?1, ?2, ?2)",
+            (word, clue),
+        )
+        .unwrap();
+        ClueWord {
+            id: conn.last_insert_rowid(),
+            word: word.to_owned(),
+            clue: clue.to_owned(),
+        }
+    }
+
+    #[test]
+    fn saved_puzzle_loads_back_identical() {
+        let db = TempDb::new("round-trip");
+        let repo = Repo::open(&db.0).unwrap();
+        repo.migrate(NaiveDate::from_ymd_opt(2026, 1, 1).unwrap())
+            .unwrap();
+
+        // A double word square: the rows and columns are the same words.
+        let words = ["HEART", "EMBER", "ABUSE", "RESIN", "TREND"];
+        let first_heart = add_clue(&repo, "HEART", "Ticker");
+        let second_heart = add_clue(&repo, "HEART", "Core");
+        assert_ne!(first_heart.id, second_heart.id);
+        let mut clue_words = vec![second_heart];
+        for word in &words[1..] {
+            clue_words.push(add_clue(&repo, word, &format!("Clue for {}", word)));
+        }
+
+        let puzzle = Puzzle {
+            date: "2026-01-05".to_owned(),
+            grid: Grid::daily(),
+            // Past i64::MAX, so it's stored as a negative bit pattern.
+            seed: Some(u64::MAX - 1),
+            across_words: clue_words.clone(),
+            down_words: clue_words,
+            plusword: "TREAT".to_owned(),
+            difficulty: Some(2.75),
+            clue_strategy: Some(ClueStrategy {
+                style: ClueStyle::Cryptic,
+                target_difficulty: Some(3.5),
+            }),
+            status: PuzzleStatus::Approved,
+        };
+        assert!(repo.save_puzzle(&puzzle).unwrap());
+
+        let (stored, number) = repo.load_puzzle(&puzzle.date).unwrap().unwrap();
+        assert_eq!(stored, puzzle);
+        assert_eq!(stored.across_words[0].clue, "Core");
+        assert_eq!(number, 1);
+
+        // A second save for the date keeps the first.
+        assert!(!repo.save_puzzle(&puzzle).unwrap());
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use crate::models::{ClueWord, Hint};
+
+/// Easiest and hardest possible ratings.
+pub const MIN_RATING: f64 = 1.0;
+pub const MAX_RATING: f64 = 5.0;
+
+/// An answer clued this many times or more counts as fully familiar.
+const FAMILIAR_CLUE_COUNT: usize = 20;
+
+/// Words in a clue at or below which it's considered terse, and at or above
+/// which it's considered generous.
+const TERSE_CLUE_WORDS: usize = 2;
+const GENEROUS_CLUE_WORDS: usize = 8;
+
+/// Phrases that usually signal cryptic wordplay (anagrams, containers,
+/// reversals, hidden words and homophones) rather than a plain definition.
+const WORDPLAY_MARKERS: &[&str] = &[
+    "about",
+    "abroad",
+    "around",
+    "back",
+    "badly",
+    "broken",
+    "confused",
+    "crazy",
+    "heard",
+    "hidden",
+    "holding",
+    "in part",
+    "mixed",
+    "novel",
+    "oddly",
+    "part of",
+    "reportedly",
+    "returned",
+    "reversed",
+    "said",
+    "some",
+    "strange",
+    "upset",
+    "we hear",
+    "wild",
+];
+
+/// English letter frequencies in percent, A to Z.
+const LETTER_FREQUENCY: [f64; 26] = [
+    8.2, 1.5, 2.8, 4.3, 12.7, 2.2, 2.0, 6.1, 7.0, 0.15, 0.77, 4.0, 2.4, 6.7, 7.5, 1.9, 0.095, 6.0,
+    6.3, 9.1, 2.8, 0.98, 2.4, 0.15, 2.0, 0.074,
+];
+
+/// Average letter rarity of typical and of very awkward fills, used to
+/// stretch the letter component over the full range.
+const COMMON_LETTER_RARITY: f64 = 0.55;
+const RARE_LETTER_RARITY: f64 = 0.85;
+
+/// Everything a rating is based on, so the breakdown can be logged.
+#[derive(Clone, Copy, Debug)]
+pub struct Factors {
+    /// 0 when every answer is among the commonest in the word list, 1 when
+    /// every one is among the rarest. Answers the list gives no frequency
+    /// for count by how often they've been clued instead.
+    pub obscurity: f64,
+    /// 0 for long plain definitions, 1 for terse clues full of wordplay.
+    pub clue_trickiness: f64,
+    /// 0 when some row's hint marks every plusword letter green, 1 when
+    /// none does. Grids with fewer or shorter across rows confirm fewer.
+    pub unconfirmed_letters: f64,
+    /// 0 for fills made of common letters, 1 for ones full of J, Q, X, Z.
+    pub letter_rarity: f64,
+}
+
+impl Factors {
+    /// Weighted blend of the factors, scaled to `MIN_RATING..=MAX_RATING`
+    /// and rounded to one decimal place.
+    pub fn rating(&self) -> f64 {
+        let blend = 0.35 * self.obscurity
+            + 0.3 * self.clue_trickiness
+            + 0.15 * self.unconfirmed_letters
+            + 0.2 * self.letter_rarity;
+        let rating = MIN_RATING + (MAX_RATING - MIN_RATING) * blend.clamp(0.0, 1.0);
+        (rating * 10.0).round() / 10.0
+    }
+}
+
+/// How many clues each answer has in `clue_words`, keyed by upper-cased
+/// word. Frequently clued answers are the ones solvers see most often.
+pub fn clue_counts(clue_words: &[ClueWord]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for clue_word in clue_words {
+        *counts.entry(clue_word.word.to_uppercase()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Works out the difficulty factors for a filled puzzle.
+///
+/// `hints` are the grid's hint colours, as from `generator::hints`, and
+/// `commonness` is the word list's commonness (0 to 1) keyed by upper-cased
+/// word.
+pub fn assess(
+    answers: &[ClueWord],
+    plusword: &str,
+    hints: &[Vec<Option<Hint>>],
+    clue_counts: &HashMap<String, usize>,
+    commonness: &HashMap<String, f64>,
+) -> Factors {
+    Factors {
+        obscurity: obscurity(answers, clue_counts, commonness),
+        clue_trickiness: mean(answers.iter().map(|cw| clue_trickiness(&cw.clue))),
+        unconfirmed_letters: unconfirmed_letters(plusword, hints),
+        letter_rarity: letter_rarity(answers.iter().map(|cw| cw.word.as_str()).chain([plusword])),
+    }
+}
+
+fn obscurity(
+    answers: &[ClueWord],
+    clue_counts: &HashMap<String, usize>,
+    commonness: &HashMap<String, f64>,
+) -> f64 {
+    let familiarity = answers.iter().map(|cw| {
+        let word = cw.word.to_uppercase();
+        if let Some(&commonness) = commonness.get(&word) {
+            return commonness;
+        }
+        let count = clue_counts.get(&word).copied().unwrap_or(0);
+        ((1 + count) as f64).ln() / ((1 + FAMILIAR_CLUE_COUNT) as f64).ln()
+    });
+    1.0 - mean(familiarity.map(|f| f.clamp(0.0, 1.0)))
+}
+
+/// The share of the plusword's letters no row's hint marks green.
+fn unconfirmed_letters(plusword: &str, hints: &[Vec<Option<Hint>>]) -> f64 {
+    let confirmed = |col: usize| {
+        hints
+            .iter()
+            .any(|row| row.get(col) == Some(&Some(Hint::Green)))
+    };
+    mean((0..plusword.chars().count()).map(|col| if confirmed(col) { 0.0 } else { 1.0 }))
+}
+
+/// How hard a single clue reads, from 0 (a long plain definition) to 1 (a
+/// terse clue full of wordplay).
+pub fn clue_trickiness(clue: &str) -> f64 {
+    let words = clue_words(clue);
+    let terseness = if words.len() <= TERSE_CLUE_WORDS {
+        1.0
+    } else if words.len() >= GENEROUS_CLUE_WORDS {
+        0.0
+    } else {
+        (GENEROUS_CLUE_WORDS - words.len()) as f64 / (GENEROUS_CLUE_WORDS - TERSE_CLUE_WORDS) as f64
+    };
+    let wordplay = (wordplay_markers(clue) as f64 / 2.0).min(1.0);
+
+    0.5 * terseness + 0.5 * wordplay
+}
+
+/// Whether a clue looks like cryptic wordplay rather than a straight
+/// definition.
+pub fn is_cryptic(clue: &str) -> bool {
+    wordplay_markers(clue) > 0
+}
+
+fn clue_words(clue: &str) -> Vec<String> {
+    clue.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn wordplay_markers(clue: &str) -> usize {
+    // Match markers on word boundaries so "some" doesn't fire on "handsome".
+    let padded = format!(" {} ", clue_words(clue).join(" "));
+    let mut markers = WORDPLAY_MARKERS
+        .iter()
+        .filter(|marker| padded.contains(&format!(" {} ", marker)))
+        .count();
+    // A trailing question mark is the setter admitting to a pun.
+    if clue.trim_end().ends_with('?') {
+        markers += 1;
+    }
+    markers
+}
+
+fn letter_rarity<'a>(words: impl Iterator<Item = &'a str>) -> f64 {
+    let max_frequency = LETTER_FREQUENCY.iter().cloned().fold(0.0, f64::max);
+    let rarity = words
+        .flat_map(str::bytes)
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| 1.0 - LETTER_FREQUENCY[(b.to_ascii_uppercase() - b'A') as usize] / max_frequency);
+    let average = mean(rarity);
+    ((average - COMMON_LETTER_RARITY) / (RARE_LETTER_RARITY - COMMON_LETTER_RARITY)).clamp(0.0, 1.0)
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
+
+/// The rating to aim for on `date`: easiest on Monday, building up to the
+/// hardest on Saturday, with Sunday in the middle.
+pub fn weekly_target(date: NaiveDate) -> f64 {
+    match date.weekday() {
+        Weekday::Mon => 1.5,
+        Weekday::Tue => 2.0,
+        Weekday::Wed => 2.5,
+        Weekday::Thu => 3.0,
+        Weekday::Fri => 3.5,
+        Weekday::Sat => 4.0,
+        Weekday::Sun => 2.75,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator;
+    use crate::grid::Grid;
+
+    fn answers(words: &[&str]) -> Vec<ClueWord> {
+        words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| ClueWord {
+                id: i as i64,
+                word: word.to_string(),
+                clue: "Part of a test".to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rarer_answers_rate_harder() {
+        let answers = answers(&["HEART", "EMBER"]);
+        let commonness =
+            |value: f64| HashMap::from([("HEART".to_owned(), value), ("EMBER".to_owned(), value)]);
+        let rate = |commonness: &HashMap<String, f64>| {
+            assess(&answers, "STARE", &[], &HashMap::new(), commonness)
+        };
+
+        let common = rate(&commonness(0.9));
+        let rare = rate(&commonness(0.1));
+        assert!((common.obscurity - 0.1).abs() < 1e-9);
+        assert!((rare.obscurity - 0.9).abs() < 1e-9);
+        assert!(rare.rating() > common.rating());
+
+        // Without frequencies, often-clued answers count as common.
+        let clued = HashMap::from([("HEART".to_owned(), 50), ("EMBER".to_owned(), 50)]);
+        let familiar = assess(&answers, "STARE", &[], &clued, &HashMap::new());
+        assert_eq!(familiar.obscurity, 0.0);
+    }
+
+    #[test]
+    fn grids_that_leave_a_column_unhinted_rate_harder() {
+        let answers = answers(&["SHORE", "ATTIC", "CHART"]);
+        let full = Grid::daily();
+        let full_hints = generator::hints(
+            "STARE",
+            &full,
+            &["SHORE", "ATTIC", "CHART", "BLOWN", "DUMPY"].map(str::to_owned),
+        )
+        .unwrap();
+        // The last column is blocked, so no row can show the final E.
+        let notched = Grid::from_rows(&["....#"; 5]).unwrap();
+        let notched_hints = generator::hints(
+            "STARE",
+            &notched,
+            &["SHOR", "ATTI", "CHAR", "BLOW", "DUMP"].map(str::to_owned),
+        )
+        .unwrap();
+
+        let open = assess(
+            &answers,
+            "STARE",
+            &full_hints,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let closed = assess(
+            &answers,
+            "STARE",
+            &notched_hints,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        assert_eq!(open.unconfirmed_letters, 0.0);
+        assert!((closed.unconfirmed_letters - 0.2).abs() < 1e-9);
+        assert!(closed.rating() > open.rating());
+    }
+}
@@ -0,0 +1,273 @@
+//! Errors from the database, the generator and the subcommands, and from
+//! the API. Each API
+//! error is answered with a JSON body naming it, such as
+//! `{"error": "not_found", "message": "no puzzle for 2025-01-01"}`.
+
+use std::fmt;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use rusqlite::ErrorCode;
+use serde::Serialize;
+
+/// Why a `Repo` call or puzzle generation failed, kept apart so a handler
+/// can tell a missing row from a locked database.
+#[derive(Debug)]
+pub enum Error {
+    /// SQLite refused the query, perhaps because another connection holds
+    /// a lock.
+    Database(rusqlite::Error),
+    /// No connection to the database could be had.
+    Pool(r2d2::Error),
+    /// The schema is out of date; `quinta migrate` brings it up to date.
+    Schema(String),
+    /// Something asked for isn't stored, such as a clue id.
+    NotFound(String),
+    /// Something stored doesn't make sense, such as an unknown status.
+    Data(String),
+    /// An argument doesn't make sense, such as a malformed date.
+    Invalid(String),
+    /// No puzzle could be generated; says which freshness rule was to
+    /// blame, if any.
+    Generation(String),
+    /// The configuration file or environment doesn't make sense.
+    Config(String),
+    /// A file couldn't be read or written; the message says which.
+    Io(String, std::io::Error),
+    /// Puzzles couldn't be fetched from the source site.
+    Fetch(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Whether the database was too busy to answer, so trying again later
+    /// may work.
+    pub fn is_busy(&self) -> bool {
+        match self {
+            Error::Database(e) => matches!(
+                e.sqlite_error_code(),
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+            ),
+            Error::Pool(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "database error: {}", e),
+            Error::Pool(e) => write!(f, "DB connection failed: {}", e),
+            Error::Schema(message)
+            | Error::NotFound(message)
+            | Error::Data(message)
+            | Error::Invalid(message)
+            | Error::Generation(message)
+            | Error::Fetch(message) => write!(f, "{}", message),
+            Error::Config(message) => write!(f, "invalid configuration: {}", message),
+            Error::Io(message, e) => write!(f, "{}: {}", message, e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Database(e) => Some(e),
+            Error::Pool(e) => Some(e),
+            Error::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<r2d2::Error> for Error {
+    fn from(e: r2d2::Error) -> Self {
+        Error::Pool(e)
+    }
+}
+
+/// How many seconds a client is asked to wait before trying again for a
+/// puzzle that's being generated.
+pub const RETRY_AFTER_SECS: u32 = 5;
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request doesn't make sense, such as a malformed date.
+    BadRequest(String),
+    /// An admin request without the admin token.
+    Unauthorized,
+    /// The puzzle for this date isn't out yet.
+    NotAvailable(String),
+    /// What the request is for doesn't exist, such as a date's puzzle.
+    NotFound(String),
+    /// The request clashes with what's stored, such as editing a published
+    /// puzzle.
+    Conflict(String),
+    /// A puzzle is being generated; the client should try again shortly.
+    Generating(String),
+    /// The database is busy; the client should try again shortly.
+    Busy,
+    /// The puzzle for this date is waiting for an editor's approval.
+    AwaitingReview(String),
+    /// Anything else. The details are logged rather than sent to the client.
+    Internal(String),
+}
+
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+impl ApiError {
+    /// Error handler for the query, path and JSON extractors, so a request
+    /// they can't parse gets the same kind of body as any other error.
+    pub fn from_extractor<E: fmt::Display>(e: E, _: &HttpRequest) -> actix_web::Error {
+        ApiError::BadRequest(e.to_string()).into()
+    }
+
+    /// The name for the error in response bodies.
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::NotAvailable(_) => "not_available",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Generating(_) => "generating",
+            ApiError::Busy => "busy",
+            ApiError::AwaitingReview(_) => "awaiting_review",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(message)
+            | ApiError::NotFound(message)
+            | ApiError::Conflict(message) => write!(f, "{}", message),
+            ApiError::Unauthorized => write!(f, "admin token required"),
+            ApiError::NotAvailable(date) => write!(f, "puzzle for {} is not available yet", date),
+            ApiError::Generating(date) => write!(
+                f,
+                "the puzzle for {} is being generated; try again shortly",
+                date
+            ),
+            ApiError::Busy => write!(f, "the database is busy; try again shortly"),
+            ApiError::AwaitingReview(date) => {
+                write!(f, "the puzzle for {} is still awaiting review", date)
+            }
+            ApiError::Internal(message) => write!(f, "error: {}", message),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::NotAvailable(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Generating(_) | ApiError::Busy | ApiError::AwaitingReview(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            ApiError::Internal(_) => {
+                log::error!("{}", self);
+                "internal server error".to_owned()
+            }
+            _ => self.to_string(),
+        };
+        let mut response = json_response(self.status_code(), self.code(), message);
+        if let ApiError::Generating(_) | ApiError::Busy = self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                header::HeaderValue::from(RETRY_AFTER_SECS),
+            );
+        }
+        response
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::NotFound(message) => ApiError::NotFound(message),
+            Error::Invalid(message) => ApiError::BadRequest(message),
+            e if e.is_busy() => {
+                log::warn!("{}", e);
+                ApiError::Busy
+            }
+            e => ApiError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<actix_web::error::BlockingError> for ApiError {
+    fn from(e: actix_web::error::BlockingError) -> Self {
+        ApiError::Internal(e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+}
+
+/// An error response in the API's JSON shape.
+fn json_response(status: StatusCode, code: &'static str, message: String) -> HttpResponse {
+    HttpResponse::build(status).json(ErrorBody {
+        error: code,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn a_locked_database_is_busy_rather_than_internal() {
+        let path = std::env::temp_dir().join(format!("quinta-busy-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let holder = Connection::open(&path).unwrap();
+        holder
+            .execute_batch("CREATE TABLE t (x); BEGIN EXCLUSIVE;")
+            .unwrap();
+
+        let other = Connection::open(&path).unwrap();
+        other.busy_timeout(std::time::Duration::ZERO).unwrap();
+        let locked = Error::from(other.execute("INSERT INTO t VALUES (1)", []).unwrap_err());
+        assert!(locked.is_busy());
+        assert!(matches!(ApiError::from(locked), ApiError::Busy));
+
+        drop((holder, other));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn keeps_missing_rows_and_bad_arguments_apart_from_failures() {
+        let missing = ApiError::from(Error::NotFound("no clue with id 7".to_owned()));
+        assert!(matches!(&missing, ApiError::NotFound(message) if message == "no clue with id 7"));
+        let invalid = ApiError::from(Error::Invalid("invalid date 2025-02-30".to_owned()));
+        assert!(matches!(invalid, ApiError::BadRequest(_)));
+        let corrupt = ApiError::from(Error::Data("bad status x for 2025-01-01".to_owned()));
+        assert_eq!(corrupt.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
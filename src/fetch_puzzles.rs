@@ -0,0 +1,397 @@
+//! `quinta fetch-puzzles`: downloads Telegraph Plusword puzzles from
+//! lettersolver.com into a JSON file for `import_puzzles`.
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{self, Error};
+use crate::js_object;
+
+const BASE_URL: &str = "https://lettersolver.com/crosswords/telegraph-plusword";
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// How many days to fetch, ending with --to
+    #[arg(default_value_t = 30)]
+    days: u64,
+
+    /// First date to fetch, instead of counting back DAYS
+    #[arg(long)]
+    from: Option<NaiveDate>,
+
+    /// Last date to fetch (default today in the UK)
+    #[arg(long)]
+    to: Option<NaiveDate>,
+
+    /// Where to write the puzzles; dates already in it are skipped
+    #[arg(long, default_value = "puzzles.json")]
+    out: PathBuf,
+
+    /// Where downloaded pages are kept
+    #[arg(long, default_value = ".fetch_cache")]
+    cache: PathBuf,
+
+    /// Least time between requests to the site
+    #[arg(long, default_value_t = 1000)]
+    delay_ms: u64,
+
+    /// Only read pages from the cache
+    #[arg(long)]
+    offline: bool,
+}
+
+/// One clue as the page lists it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FetchedClue {
+    pub clue: String,
+    pub slug: String,
+    pub word: String,
+    pub dir: String,
+    pub num: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FetchedPuzzle {
+    pub date: String,
+    pub clues: Vec<FetchedClue>,
+}
+
+struct Options {
+    from: NaiveDate,
+    to: NaiveDate,
+    out: PathBuf,
+    cache: PathBuf,
+    delay: Duration,
+    offline: bool,
+}
+
+impl Options {
+    fn from_args(args: Args) -> error::Result<Self> {
+        // Plusword is published at midnight UK time, whatever our own zone.
+        let to = args.to.unwrap_or_else(|| {
+            chrono::Utc::now()
+                .with_timezone(&chrono_tz::Europe::London)
+                .date_naive()
+        });
+        let from = match args.from {
+            Some(from) => from,
+            None => to
+                .checked_sub_days(Days::new(args.days.saturating_sub(1)))
+                .ok_or_else(|| Error::Invalid("date range out of bounds".to_owned()))?,
+        };
+        if from > to {
+            return Err(Error::Invalid(format!(
+                "--from {} is after --to {}",
+                from, to
+            )));
+        }
+        Ok(Options {
+            from,
+            to,
+            out: args.out,
+            cache: args.cache,
+            delay: Duration::from_millis(args.delay_ms),
+            offline: args.offline,
+        })
+    }
+}
+
+/// Where a page comes from: the on-disk cache if it's there, otherwise the
+/// site, at most one request per `delay`.
+struct PageSource {
+    cache: PathBuf,
+    delay: Duration,
+    offline: bool,
+    last_request: Option<Instant>,
+}
+
+impl PageSource {
+    fn cache_path(&self, date: NaiveDate) -> PathBuf {
+        self.cache.join(format!("{}.html", date))
+    }
+
+    /// The page for `date`, or `None` if the site has none (or, offline,
+    /// it isn't cached). Only pages with the answers are cached, so dates
+    /// whose answers aren't up yet are tried again next time.
+    fn page(&mut self, date: NaiveDate) -> error::Result<Option<String>> {
+        if let Some(html) = self.cached(date)? {
+            return Ok(Some(html));
+        }
+        if self.offline {
+            return Ok(None);
+        }
+
+        if let Some(last) = self.last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.delay {
+                thread::sleep(self.delay - elapsed);
+            }
+        }
+        self.last_request = Some(Instant::now());
+
+        let url = format!("{}/{}/", BASE_URL, date);
+        let html = match ureq::get(&url).call() {
+            Ok(response) => response
+                .into_string()
+                .map_err(|e| Error::Io(format!("reading {}", url), e))?,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            // ureq's errors already name the URL.
+            Err(e) => return Err(Error::Fetch(e.to_string())),
+        };
+
+        self.store(date, &html)?;
+        Ok(Some(html))
+    }
+
+    /// The cached page for `date`, if any. Online, a cached page without
+    /// the answers counts as missing, so it's fetched again; offline it's
+    /// all there is.
+    fn cached(&self, date: NaiveDate) -> error::Result<Option<String>> {
+        let path = self.cache_path(date);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let html = fs::read_to_string(&path)
+            .map_err(|e| Error::Io(format!("reading {}", path.display()), e))?;
+        Ok((self.offline || has_clues(&html)).then_some(html))
+    }
+
+    /// Caches the page for `date`, unless it hasn't got the answers yet.
+    fn store(&self, date: NaiveDate, html: &str) -> error::Result<()> {
+        if !has_clues(html) {
+            return Ok(());
+        }
+        let path = self.cache_path(date);
+        fs::create_dir_all(&self.cache)
+            .map_err(|e| Error::Io(format!("creating {}", self.cache.display()), e))?;
+        fs::write(&path, html).map_err(|e| Error::Io(format!("writing {}", path.display()), e))
+    }
+}
+
+/// The contents of every `<script>` element in `html`.
+fn scripts(html: &str) -> Vec<&str> {
+    let lower = html.to_ascii_lowercase();
+    let mut scripts = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<script").map(|i| pos + i) {
+        let Some(body) = lower[start..].find('>').map(|i| start + i + 1) else {
+            break;
+        };
+        let Some(end) = lower[body..].find("</script").map(|i| body + i) else {
+            break;
+        };
+        scripts.push(&html[body..end]);
+        pos = end;
+    }
+    scripts
+}
+
+/// The clues from a puzzle page: the `items` array of the
+/// `recentSourceClues` object in its scripts. `None` if the page has none.
+fn extract_clues(html: &str) -> Result<Option<Vec<FetchedClue>>, String> {
+    for script in scripts(html) {
+        let Some(start) = script.find("recentSourceClues") else {
+            continue;
+        };
+
+        let rest = &script[start..];
+        let items = rest.match_indices("items").find_map(|(i, _)| {
+            let after = rest[i + "items".len()..]
+                .trim_start_matches(['"', '\''])
+                .trim_start();
+            let after = after.strip_prefix(':')?.trim_start();
+            after.starts_with('[').then_some(after)
+        });
+        let Some(items) = items else {
+            return Err("recentSourceClues has no items array".to_owned());
+        };
+
+        let (value, _) = js_object::parse_prefix(items)?;
+        let clues = serde_json::from_value(value).map_err(|e| format!("unexpected clue: {}", e))?;
+        return Ok(Some(clues));
+    }
+    Ok(None)
+}
+
+/// Whether `html` is a puzzle page with its answers up.
+fn has_clues(html: &str) -> bool {
+    matches!(extract_clues(html), Ok(Some(_)))
+}
+
+/// Puzzles already in `path`, by date, so a run can pick up where the last
+/// one stopped.
+fn load_existing(path: &Path) -> error::Result<BTreeMap<String, FetchedPuzzle>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let json = fs::read_to_string(path)
+        .map_err(|e| Error::Io(format!("reading {}", path.display()), e))?;
+    let puzzles: Vec<FetchedPuzzle> = serde_json::from_str(&json)
+        .map_err(|e| Error::Invalid(format!("parsing {}: {}", path.display(), e)))?;
+    Ok(puzzles.into_iter().map(|p| (p.date.clone(), p)).collect())
+}
+
+/// Writes the puzzles newest first, via a temporary file so an interrupted
+/// run never leaves a half-written file behind.
+fn save(path: &Path, puzzles: &BTreeMap<String, FetchedPuzzle>) -> error::Result<()> {
+    let newest_first: Vec<&FetchedPuzzle> = puzzles.values().rev().collect();
+    let json = serde_json::to_string_pretty(&newest_first).unwrap();
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| Error::Io(format!("writing {}", tmp.display()), e))?;
+    fs::rename(&tmp, path).map_err(|e| Error::Io(format!("writing {}", path.display()), e))
+}
+
+pub fn run(args: Args) -> error::Result<()> {
+    let options = Options::from_args(args)?;
+    let mut puzzles = load_existing(&options.out)?;
+    let mut source = PageSource {
+        cache: options.cache.clone(),
+        delay: options.delay,
+        offline: options.offline,
+        last_request: None,
+    };
+
+    println!(
+        "Fetching puzzles from {} to {}{}",
+        options.from,
+        options.to,
+        if options.offline { " (offline)" } else { "" }
+    );
+
+    let (mut fetched, mut already, mut missing, mut failed) = (0, 0, 0, 0);
+    let mut date = options.to;
+    while date >= options.from {
+        let key = date.to_string();
+        if puzzles.contains_key(&key) {
+            already += 1;
+        } else {
+            println!("Fetching puzzle for {}...", date);
+            match source.page(date).and_then(|page| match page {
+                Some(html) => extract_clues(&html).map_err(Error::Fetch),
+                None => Ok(None),
+            }) {
+                Ok(Some(clues)) => {
+                    println!("  ✓ Found puzzle for {} ({} clues)", date, clues.len());
+                    puzzles.insert(key.clone(), FetchedPuzzle { date: key, clues });
+                    save(&options.out, &puzzles)?;
+                    fetched += 1;
+                }
+                Ok(None) => {
+                    println!("  ✗ No puzzle found for {}", date);
+                    missing += 1;
+                }
+                Err(e) => {
+                    eprintln!("  ✗ Failed for {}: {}", date, e);
+                    failed += 1;
+                }
+            }
+        }
+
+        let Some(previous) = date.checked_sub_days(Days::new(1)) else {
+            break;
+        };
+        date = previous;
+    }
+
+    save(&options.out, &puzzles)?;
+
+    println!("\n======================");
+    println!("Fetch Summary:");
+    println!("  Fetched:         {}", fetched);
+    println!("  Already had:     {}", already);
+    println!("  Not found:       {}", missing);
+    println!("  Failed:          {}", failed);
+    println!("\nPuzzles saved to {}", options.out.display());
+
+    if failed > 0 {
+        return Err(Error::Fetch(format!("{} date(s) failed", failed)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_clues_from_a_saved_page() {
+        let page = include_str!("../fixtures/fetch/pages/2026-01-05.html");
+        let expected: Vec<FetchedPuzzle> =
+            serde_json::from_str(include_str!("../fixtures/fetch/puzzles.json")).unwrap();
+
+        let clues = extract_clues(page).unwrap().expect("the page has clues");
+        assert_eq!(clues.len(), 11);
+        assert_eq!(
+            serde_json::to_value(&clues).unwrap(),
+            serde_json::to_value(&expected[0].clues).unwrap()
+        );
+        let multi_line = clues
+            .iter()
+            .find(|clue| clue.num == 3 && clue.dir == "down");
+        assert_eq!(multi_line.unwrap().clue, "Multiline clue");
+    }
+
+    #[test]
+    fn finds_no_clues_on_a_page_without_answers() {
+        let page = include_str!("../fixtures/fetch/pages/2026-01-04.html");
+        assert!(extract_clues(page).unwrap().is_none());
+    }
+
+    /// A page source reading from a fresh cache directory named `name`.
+    fn source(name: &str, offline: bool) -> PageSource {
+        let cache = std::env::temp_dir().join(format!("quinta-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&cache);
+        fs::create_dir_all(&cache).unwrap();
+        PageSource {
+            cache,
+            delay: Duration::ZERO,
+            offline,
+            last_request: None,
+        }
+    }
+
+    #[test]
+    fn a_cached_page_without_answers_is_fetched_again() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        let page = include_str!("../fixtures/fetch/pages/2026-01-04.html");
+        for offline in [false, true] {
+            let source = source(&format!("cached-{}", offline), offline);
+            fs::write(source.cache_path(date), page).unwrap();
+            let cached = source.cached(date).unwrap();
+            assert_eq!(cached.is_some(), offline, "offline: {}", offline);
+            fs::remove_dir_all(&source.cache).unwrap();
+        }
+    }
+
+    #[test]
+    fn caches_only_pages_with_answers() {
+        let source = source("store", false);
+        let without = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        let with = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let page_without = include_str!("../fixtures/fetch/pages/2026-01-04.html");
+        let page_with = include_str!("../fixtures/fetch/pages/2026-01-05.html");
+
+        source.store(without, page_without).unwrap();
+        source.store(with, page_with).unwrap();
+        assert!(!source.cache_path(without).exists());
+        assert_eq!(source.cached(with).unwrap().as_deref(), Some(page_with));
+        fs::remove_dir_all(&source.cache).unwrap();
+    }
+
+    #[test]
+    fn rejects_clues_without_items() {
+        let page = "<script>x={recentSourceClues:{total:0}}</script>";
+        assert!(extract_clues(page).is_err());
+    }
+
+    #[test]
+    fn finds_scripts_whatever_their_case() {
+        let html = "<p>x</p><script>a</script><SCRIPT type=\"x\">b</SCRIPT>";
+        assert_eq!(scripts(html), vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use crate::models::ClueWord;
+
+/// How long answers and clues are kept out of rotation after they've been
+/// used. Pluswords are never reused at all.
+#[derive(Clone, Copy, Debug)]
+pub struct Policy {
+    /// An answer can't appear within this many days of a puzzle using it.
+    pub word_days: i64,
+    /// Clue text can't appear within this many days of a puzzle using it.
+    pub clue_days: i64,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            word_days: 30,
+            clue_days: 365,
+        }
+    }
+}
+
+/// One of the rules in a `Policy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Constraint {
+    Word,
+    Clue,
+    Plusword,
+}
+
+impl Constraint {
+    pub const ALL: [Constraint; 3] = [Constraint::Word, Constraint::Clue, Constraint::Plusword];
+}
+
+/// A puzzle already in the database, reduced to what freshness cares about.
+pub struct PastPuzzle {
+    pub date: NaiveDate,
+    pub clue_word_ids: Vec<i64>,
+    pub plusword: String,
+}
+
+/// What a puzzle for one date may not use, worked out from the puzzles
+/// around it.
+#[derive(Clone, Debug, Default)]
+pub struct Exclusions {
+    policy: Policy,
+    words: HashSet<String>,
+    clues: HashSet<String>,
+    pluswords: HashSet<String>,
+    relaxed: Vec<Constraint>,
+}
+
+impl Exclusions {
+    /// Collects the answers, clues and pluswords that `policy` rules out for
+    /// `date`, given the other stored puzzles on both sides of it, since a
+    /// date may be generated after later ones, as when it's replaced.
+    /// Whatever is stored for `date` itself never counts, since a new puzzle
+    /// replaces it.
+    pub fn for_date(
+        policy: Policy,
+        date: NaiveDate,
+        history: &[PastPuzzle],
+        clue_words: &[ClueWord],
+    ) -> Self {
+        let by_id: HashMap<i64, &ClueWord> = clue_words.iter().map(|cw| (cw.id, cw)).collect();
+        let mut exclusions = Exclusions {
+            policy,
+            ..Exclusions::default()
+        };
+
+        for past in history.iter().filter(|past| past.date != date) {
+            exclusions
+                .pluswords
+                .insert(past.plusword.to_ascii_uppercase());
+
+            let days_apart = (date - past.date).num_days().abs();
+            let words_recent = days_apart < policy.word_days;
+            let clues_recent = days_apart < policy.clue_days;
+            if !words_recent && !clues_recent {
+                continue;
+            }
+            for clue_word in past.clue_word_ids.iter().filter_map(|id| by_id.get(id)) {
+                if words_recent {
+                    exclusions.words.insert(clue_word.word.to_ascii_uppercase());
+                }
+                if clues_recent {
+                    exclusions.clues.insert(normalize_clue(&clue_word.clue));
+                }
+            }
+        }
+
+        exclusions
+    }
+
+    /// A copy with `constraint` no longer enforced, for working out which
+    /// rule stood in the way of a failed generation.
+    pub fn without(&self, constraint: Constraint) -> Self {
+        let mut relaxed = self.clone();
+        relaxed.relaxed.push(constraint);
+        relaxed
+    }
+
+    /// Whether `constraint` rules anything out at all.
+    pub fn excludes_any(&self, constraint: Constraint) -> bool {
+        self.enforces(constraint)
+            && match constraint {
+                Constraint::Word => !self.words.is_empty(),
+                Constraint::Clue => !self.clues.is_empty(),
+                Constraint::Plusword => !self.pluswords.is_empty(),
+            }
+    }
+
+    fn enforces(&self, constraint: Constraint) -> bool {
+        !self.relaxed.contains(&constraint)
+    }
+
+    pub fn allows_clue(&self, clue_word: &ClueWord) -> bool {
+        !(self.enforces(Constraint::Word)
+            && self.words.contains(&clue_word.word.to_ascii_uppercase())
+            || self.enforces(Constraint::Clue)
+                && self.clues.contains(&normalize_clue(&clue_word.clue)))
+    }
+
+    pub fn allows_plusword(&self, word: &str) -> bool {
+        !(self.enforces(Constraint::Plusword)
+            && self.pluswords.contains(&word.to_ascii_uppercase()))
+    }
+
+    /// The clue-word pairs a fill may draw from.
+    pub fn filter_clues(&self, clue_words: &[ClueWord]) -> Vec<ClueWord> {
+        clue_words
+            .iter()
+            .filter(|cw| self.allows_clue(cw))
+            .cloned()
+            .collect()
+    }
+
+    /// The words a plusword may be chosen from.
+    pub fn filter_pluswords(&self, words: &[String]) -> Vec<String> {
+        words
+            .iter()
+            .filter(|word| self.allows_plusword(word))
+            .cloned()
+            .collect()
+    }
+
+    /// Describes `constraint` with the numbers from this policy.
+    pub fn describe(&self, constraint: Constraint) -> String {
+        match constraint {
+            Constraint::Word => format!(
+                "no answer reused within {} days ({} excluded)",
+                self.policy.word_days,
+                self.words.len()
+            ),
+            Constraint::Clue => format!(
+                "no clue reused within {} days ({} excluded)",
+                self.policy.clue_days,
+                self.clues.len()
+            ),
+            Constraint::Plusword => format!(
+                "no plusword ever reused ({} excluded)",
+                self.pluswords.len()
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Exclusions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<String> = Constraint::ALL
+            .iter()
+            .filter(|&&c| self.enforces(c))
+            .map(|&c| self.describe(c))
+            .collect();
+        write!(f, "{}", rules.join("; "))
+    }
+}
+
+/// Clue text as compared for reuse; a change of case or spacing doesn't
+/// make a clue new.
+fn normalize_clue(clue: &str) -> String {
+    clue.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    fn clue_word(id: i64, word: &str) -> ClueWord {
+        ClueWord {
+            id,
+            word: word.to_owned(),
+            clue: format!("Clue {}", id),
+        }
+    }
+
+    fn past(date: NaiveDate, id: i64, plusword: &str) -> PastPuzzle {
+        PastPuzzle {
+            date,
+            clue_word_ids: vec![id],
+            plusword: plusword.to_owned(),
+        }
+    }
+
+    #[test]
+    fn new_puzzles_avoid_dates_on_both_sides() {
+        let clue_words = [
+            clue_word(1, "EARLY"),
+            clue_word(2, "TODAY"),
+            clue_word(3, "LATER"),
+        ];
+        let history = [
+            past(day(8), 1, "BEFORE"),
+            past(day(10), 2, "REPLACED"),
+            past(day(13), 3, "AFTER"),
+        ];
+        let policy = Policy {
+            word_days: 5,
+            clue_days: 5,
+        };
+
+        let exclusions = Exclusions::for_date(policy, day(10), &history, &clue_words);
+        assert!(!exclusions.allows_plusword("before"));
+        assert!(!exclusions.allows_plusword("after"));
+        assert!(!exclusions.allows_clue(&clue_words[0]));
+        assert!(!exclusions.allows_clue(&clue_words[2]));
+        // The puzzle being replaced doesn't count against its replacement.
+        assert!(exclusions.allows_plusword("replaced"));
+        assert!(exclusions.allows_clue(&clue_words[1]));
+    }
+
+    #[test]
+    fn answers_come_back_after_the_window() {
+        let clue_words = [clue_word(1, "AGAIN")];
+        let history = [past(day(1), 1, "ONCE"), past(day(30), 1, "TWICE")];
+        let policy = Policy {
+            word_days: 7,
+            clue_days: 7,
+        };
+        let exclusions = Exclusions::for_date(policy, day(15), &history, &clue_words);
+        assert!(exclusions.allows_clue(&clue_words[0]));
+        assert!(!exclusions.allows_plusword("ONCE"));
+        assert!(!exclusions.allows_plusword("TWICE"));
+    }
+}
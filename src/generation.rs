@@ -0,0 +1,451 @@
+//! Puzzle generation: loading what it draws on, building candidate
+//! puzzles for a date and keeping the best, and making sure only one
+//! puzzle is generated at a time.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, TryLockError};
+use std::time::Instant;
+
+use chrono::NaiveDate;
+
+use crate::clues::{ClueSelector, ClueStrategy};
+use crate::config;
+use crate::db::Repo;
+use crate::difficulty;
+use crate::error::{ApiError, ApiResult, Error, Result};
+use crate::freshness::{Constraint, Exclusions};
+use crate::generator;
+use crate::grid::Grid;
+use crate::models::{ClueWord, DailyPuzzle, Puzzle, PuzzleStatus};
+
+pub fn load_todays_puzzle(repo: &Repo, date: &str) -> Result<Option<DailyPuzzle>> {
+    repo.load_puzzle(date)?
+        .map(|(puzzle, number)| add_hints(puzzle, number))
+        .transpose()
+}
+
+/// Serialises puzzle generation within the process, so the scheduler and a
+/// request handler never brute-force the same date at the same time.
+pub static GENERATION_LOCK: Mutex<()> = Mutex::new(());
+
+/// Like `get_or_create_puzzle`, but rather than wait while another puzzle
+/// is generated, fails with `ApiError::Generating` so a request can be
+/// answered straight away.
+pub fn get_or_start_puzzle(repo: &Repo, date: &str) -> ApiResult<DailyPuzzle> {
+    if let Some(puzzle) = load_todays_puzzle(repo, date)? {
+        return Ok(puzzle);
+    }
+
+    let _guard = match GENERATION_LOCK.try_lock() {
+        Ok(guard) => guard,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return Err(ApiError::Generating(date.to_owned())),
+    };
+
+    // Someone else may have generated it before we took the lock.
+    match load_todays_puzzle(repo, date)? {
+        Some(puzzle) => Ok(puzzle),
+        None => Ok(create_puzzle(repo, date)?),
+    }
+}
+
+/// Loads the puzzle for `date`, generating and saving it first if needed.
+pub fn get_or_create_puzzle(repo: &Repo, date: &str) -> Result<DailyPuzzle> {
+    if let Some(puzzle) = load_todays_puzzle(repo, date)? {
+        return Ok(puzzle);
+    }
+
+    let _guard = GENERATION_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    // Someone else may have generated it while we waited for the lock.
+    match load_todays_puzzle(repo, date)? {
+        Some(puzzle) => Ok(puzzle),
+        None => create_puzzle(repo, date),
+    }
+}
+
+pub fn create_puzzle(repo: &Repo, date: &str) -> Result<DailyPuzzle> {
+    let overall_start = Instant::now();
+
+    log::info!("Generating new puzzle for {}", date);
+
+    let sources = Sources::load(repo)?;
+    let puzzle = build_puzzle_for_date(repo, date, date, &Grid::daily(), &sources)?;
+
+    // Another process may have saved this date first, in which case theirs
+    // wins. Either way, serve what's actually in the database.
+    repo.save_puzzle(&puzzle)?;
+
+    let total_elapsed = overall_start.elapsed();
+    log::info!(
+        "Generated the puzzle for {} in {:.2}s",
+        date,
+        total_elapsed.as_secs_f64()
+    );
+
+    load_todays_puzzle(repo, date)?
+        .ok_or_else(|| Error::Data(format!("puzzle for {} missing after save", date)))
+}
+
+/// Status for newly generated puzzles: drafts awaiting review when the
+/// admin API is on, otherwise approved straight away.
+fn new_puzzle_status() -> PuzzleStatus {
+    match config::get().admin.token {
+        Some(_) => PuzzleStatus::Draft,
+        None => PuzzleStatus::Approved,
+    }
+}
+
+/// Everything generation draws on, loaded once so several candidate puzzles
+/// can be built from it.
+pub struct Sources {
+    clue_words: Vec<ClueWord>,
+    words: Vec<String>,
+    /// Commonness of each word from the word list's frequencies, keyed by
+    /// upper-cased word; empty if no list had any.
+    commonness: HashMap<String, f64>,
+    clue_counts: HashMap<String, usize>,
+    clue_ratings: HashMap<i64, u8>,
+}
+
+impl Sources {
+    pub fn load(repo: &Repo) -> Result<Self> {
+        let blocklist =
+            repo.load_blocklist(config::get().moderation.blocklist_categories.as_deref())?;
+        let (clue_words, blocked_clues): (Vec<ClueWord>, Vec<ClueWord>) =
+            repo.load_all_clues(false)?.into_iter().partition(|cw| {
+                blocklist.check(&cw.word).is_none() && blocklist.check(&cw.clue).is_none()
+            });
+        let (words, blocked_words): (Vec<String>, Vec<String>) = repo
+            .load_words()?
+            .into_iter()
+            .partition(|word| blocklist.check(word).is_none());
+        log::debug!(
+            "Loaded {} clue-word pairs ({} blocked) and {} words ({} blocked)",
+            clue_words.len(),
+            blocked_clues.len(),
+            words.len(),
+            blocked_words.len()
+        );
+        let clue_counts = difficulty::clue_counts(&clue_words);
+        let clue_ratings = repo.load_clue_ratings()?;
+        let commonness = repo.load_word_commonness()?;
+        Ok(Sources {
+            clue_words,
+            words,
+            commonness,
+            clue_counts,
+            clue_ratings,
+        })
+    }
+
+    /// Every word the hints for `plusword` leave open once the grid is
+    /// filled with `across_words`; see `generator::plusword_candidates`.
+    pub fn plusword_candidates(
+        &self,
+        grid: &Grid,
+        across_words: &[ClueWord],
+        plusword: &str,
+    ) -> Vec<String> {
+        let across: Vec<String> = across_words.iter().map(|cw| cw.word.clone()).collect();
+        generator::plusword_candidates(&self.words, grid, &across, plusword)
+    }
+}
+
+/// The clue strategy for new puzzles on `date`: the configured style,
+/// aiming for the date's weekly difficulty target.
+pub fn clue_strategy(date: NaiveDate) -> ClueStrategy {
+    ClueStrategy {
+        style: config::get().generation.clue_style,
+        target_difficulty: Some(difficulty::weekly_target(date)),
+    }
+}
+
+/// What generation for `date` must avoid, worked out from the puzzles
+/// around it, and how it picks clues, worked out from the puzzles before
+/// it.
+pub fn load_rules(
+    repo: &Repo,
+    date: NaiveDate,
+    sources: &Sources,
+    strategy: ClueStrategy,
+) -> Result<(Exclusions, ClueSelector)> {
+    let history = repo.load_history()?;
+    let exclusions = Exclusions::for_date(
+        config::get().generation.freshness(),
+        date,
+        &history,
+        &sources.clue_words,
+    );
+    let selector = ClueSelector::new(strategy, date, &history, sources.clue_ratings.clone());
+    Ok((exclusions, selector))
+}
+
+/// How many candidate puzzles to build for a date when aiming for its
+/// weekly difficulty target.
+pub const DEFAULT_DIFFICULTY_CANDIDATES: u32 = 4;
+
+/// Builds several candidate daily puzzles for `date` and keeps the one
+/// whose difficulty is closest to `difficulty::weekly_target`. The first
+/// candidate's seed comes from `label` (normally the date itself) and the
+/// rest use seeds derived from it; whichever wins has its seed recorded, so
+/// it can be regenerated.
+pub fn build_puzzle_for_date(
+    repo: &Repo,
+    date: &str,
+    label: &str,
+    grid: &Grid,
+    sources: &Sources,
+) -> Result<Puzzle> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| Error::Invalid(format!("invalid date {}: {}", date, e)))?;
+    let target = difficulty::weekly_target(day);
+    let (exclusions, selector) = load_rules(repo, day, sources, clue_strategy(day))?;
+    log::debug!("Freshness rules: {}", exclusions);
+    log::debug!("Clue strategy: {}", selector.strategy());
+    let generation = &config::get().generation;
+    let secret = &generation.seed_secret;
+
+    let mut best: Option<(f64, Puzzle)> = None;
+    let mut last_error = None;
+    for candidate in 0..generation.difficulty_candidates {
+        let seed = match candidate {
+            0 => generator::seed_for_date(label, secret),
+            n => generator::seed_for_date(&format!("{}#{}", label, n), secret),
+        };
+        let puzzle = match fill_puzzle(date, grid, seed, sources, &exclusions, &selector) {
+            Ok(puzzle) => puzzle,
+            Err(e) => {
+                log::warn!("Candidate {} for {} failed: {}", candidate, date, e);
+                last_error = Some(e.to_string());
+                continue;
+            }
+        };
+        let distance = (puzzle.difficulty.unwrap_or(target) - target).abs();
+        if best
+            .as_ref()
+            .is_none_or(|(best_distance, _)| distance < *best_distance)
+        {
+            best = Some((distance, puzzle));
+        }
+    }
+
+    let Some((_, puzzle)) = best else {
+        let error = last_error.unwrap_or_default();
+        let seed = generator::seed_for_date(label, secret);
+        return Err(explain_failure(
+            date,
+            grid,
+            seed,
+            sources,
+            &exclusions,
+            &selector,
+            error,
+        ));
+    };
+    log::info!(
+        "Chose difficulty {:.1} for {} (target {:.1})",
+        puzzle.difficulty.unwrap_or_default(),
+        date,
+        target
+    );
+    Ok(puzzle)
+}
+
+/// Most fills `build_puzzle` tries before giving up on finding one with a
+/// deducible plusword.
+const MAX_FILL_ATTEMPTS: u64 = 10;
+
+/// Generates a puzzle of the given shape without saving it, avoiding
+/// whatever `exclusions` rules out and picking clues with `selector`. The
+/// same seed, strategy and database contents always produce the same
+/// puzzle.
+///
+/// If it fails, the error says which freshness rule was to blame, if any.
+pub fn build_puzzle(
+    date: &str,
+    grid: &Grid,
+    seed: u64,
+    sources: &Sources,
+    exclusions: &Exclusions,
+    selector: &ClueSelector,
+) -> Result<Puzzle> {
+    fill_puzzle(date, grid, seed, sources, exclusions, selector).map_err(|e| {
+        explain_failure(
+            date,
+            grid,
+            seed,
+            sources,
+            exclusions,
+            selector,
+            e.to_string(),
+        )
+    })
+}
+
+/// Works out whether a failed generation was down to the freshness rules,
+/// by retrying with each rule relaxed in turn, then with all of them.
+fn explain_failure(
+    date: &str,
+    grid: &Grid,
+    seed: u64,
+    sources: &Sources,
+    exclusions: &Exclusions,
+    selector: &ClueSelector,
+    error: String,
+) -> Error {
+    log::debug!("Checking whether freshness rules blocked generation");
+    let active: Vec<Constraint> = Constraint::ALL
+        .into_iter()
+        .filter(|&c| exclusions.excludes_any(c))
+        .collect();
+
+    for &constraint in &active {
+        let relaxed = exclusions.without(constraint);
+        if fill_puzzle(date, grid, seed, sources, &relaxed, selector).is_ok() {
+            return Error::Generation(format!(
+                "{} (blocked by freshness rule: {})",
+                error,
+                exclusions.describe(constraint)
+            ));
+        }
+    }
+
+    if active.len() > 1 {
+        let relaxed = active
+            .iter()
+            .fold(exclusions.clone(), |relaxed, &c| relaxed.without(c));
+        if fill_puzzle(date, grid, seed, sources, &relaxed, selector).is_ok() {
+            return Error::Generation(format!(
+                "{} (blocked by the freshness rules together: {})",
+                error, exclusions
+            ));
+        }
+    }
+
+    Error::Generation(error)
+}
+
+/// One go at generating a puzzle, without explaining failures.
+///
+/// If no plusword is deducible enough for a fill, the grid is filled again
+/// from a seed derived from `seed`, up to `MAX_FILL_ATTEMPTS` times.
+fn fill_puzzle(
+    date: &str,
+    grid: &Grid,
+    seed: u64,
+    sources: &Sources,
+    exclusions: &Exclusions,
+    selector: &ClueSelector,
+) -> Result<Puzzle> {
+    let clue_words = exclusions.filter_clues(&sources.clue_words);
+    let words = exclusions.filter_pluswords(&sources.words);
+    let max_candidates = config::get().generation.max_plusword_candidates;
+
+    for attempt in 0..MAX_FILL_ATTEMPTS {
+        let fill_seed = seed.wrapping_add(attempt);
+        let (across_words, down_words) = generator::generate_crossword(
+            &clue_words,
+            grid,
+            fill_seed,
+            selector,
+            &sources.commonness,
+        )
+        .map_err(|e| Error::Generation(format!("Failed to generate crossword: {}", e)))?;
+        let across: Vec<String> = across_words.iter().map(|cw| cw.word.clone()).collect();
+
+        let mut rng = generator::plusword_rng(fill_seed);
+        let Some(plusword) = generator::choose_plusword(
+            &words,
+            grid,
+            &across,
+            max_candidates,
+            &sources.commonness,
+            &mut rng,
+        ) else {
+            log::debug!(
+                "No plusword leaves at most {} candidate(s) for this fill, trying another",
+                max_candidates
+            );
+            continue;
+        };
+
+        let factors = assess(grid, &across_words, &down_words, &plusword, sources)?;
+        log::debug!(
+            "Difficulty {:.1} (obscurity {:.2}, clues {:.2}, unconfirmed {:.2}, letters {:.2})",
+            factors.rating(),
+            factors.obscurity,
+            factors.clue_trickiness,
+            factors.unconfirmed_letters,
+            factors.letter_rarity
+        );
+
+        return Ok(Puzzle {
+            date: date.to_owned(),
+            grid: grid.clone(),
+            seed: Some(seed),
+            across_words,
+            down_words,
+            plusword,
+            difficulty: Some(factors.rating()),
+            clue_strategy: Some(selector.strategy()),
+            status: new_puzzle_status(),
+        });
+    }
+
+    Err(Error::Generation(format!(
+        "no deducible {}-letter plusword found in {} fills",
+        grid.width, MAX_FILL_ATTEMPTS
+    )))
+}
+
+/// Rates a filled grid's difficulty.
+pub fn assess(
+    grid: &Grid,
+    across_words: &[ClueWord],
+    down_words: &[ClueWord],
+    plusword: &str,
+    sources: &Sources,
+) -> Result<difficulty::Factors> {
+    let across: Vec<String> = across_words.iter().map(|cw| cw.word.clone()).collect();
+    let hints = generator::hints(plusword, grid, &across).map_err(Error::Data)?;
+    let answers: Vec<ClueWord> = across_words.iter().chain(down_words).cloned().collect();
+    Ok(difficulty::assess(
+        &answers,
+        plusword,
+        &hints,
+        &sources.clue_counts,
+        &sources.commonness,
+    ))
+}
+
+/// The stored `puzzle` with its hints worked out. Fails if an answer
+/// doesn't fit its entry, as an edit to the database could leave it.
+pub fn add_hints(puzzle: Puzzle, number: i64) -> Result<DailyPuzzle> {
+    let hints = generator::hints(
+        &puzzle.plusword,
+        &puzzle.grid,
+        &puzzle
+            .across_words
+            .iter()
+            .map(|cw| cw.word.clone())
+            .collect::<Vec<String>>(),
+    )
+    .map_err(|e| Error::Data(format!("puzzle for {}: {}", puzzle.date, e)))?;
+
+    Ok(DailyPuzzle {
+        date: puzzle.date,
+        number,
+        grid: puzzle.grid,
+        seed: puzzle.seed,
+        across_words: puzzle.across_words,
+        down_words: puzzle.down_words,
+        hints,
+        plusword: puzzle.plusword,
+        difficulty: puzzle.difficulty,
+        clue_strategy: puzzle.clue_strategy,
+        status: puzzle.status,
+    })
+}
@@ -3,220 +3,295 @@ use std::{
     time::Instant,
 };
 
+use crate::clues::ClueSelector;
+use crate::grid::{Direction, Entry, Grid};
+use crate::hints;
 use crate::models::{ClueWord, Hint};
+use crate::solver::{self, Budget, Slot, SolveError, WordIndex};
 
-pub fn choose_plusword(words: &Vec<String>, across_words: &Vec<String>) -> String {
-    let mut map: HashMap<usize, i32> = HashMap::new();
-
-    for (index, plusword) in words.iter().enumerate() {
-        let plusword_hints = hints(plusword, across_words);
-        let mut count = 0;
-        for word in words.iter() {
-            let hints = hints(word, across_words);
-            if hints_eq(hints, plusword_hints) {
-                count += 1;
-            }
-        }
-        map.insert(index, count);
-    }
-    let min = map.iter().min_by_key(|(_, v)| **v).unwrap();
+/// How many words a plusword's hints may leave possible by default. With 1
+/// the hints always pin down a single answer.
+pub const DEFAULT_MAX_PLUSWORD_CANDIDATES: usize = 1;
 
-    words[*min.0].to_owned()
+/// The commonness assumed for a word the word list gives no frequency for.
+const UNKNOWN_COMMONNESS: f64 = 0.5;
+
+/// How much to favour `word`: from 0.5 for the rarest word in the list to
+/// 1.5 for the most common. `commonness` is keyed by upper-cased word.
+fn familiarity(commonness: &HashMap<String, f64>, word: &str) -> f64 {
+    0.5 + commonness
+        .get(&word.to_ascii_uppercase())
+        .copied()
+        .unwrap_or(UNKNOWN_COMMONNESS)
 }
 
-fn hints_eq(hint_a: [[Option<Hint>; 5]; 5], hint_b: [[Option<Hint>; 5]; 5]) -> bool {
-    for i in 0..5 {
-        for j in 0..5 {
-            if hint_a[i][j] != hint_b[i][j] {
-                return false;
-            }
-        }
-    }
-    true
+/// The distinct, upper-cased words of `length` letters from `words`, in the
+/// order first seen.
+fn plusword_pool(words: &[String], length: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    words
+        .iter()
+        .map(|w| w.to_ascii_uppercase())
+        .filter(|w| w.len() == length && w.bytes().all(|b| b.is_ascii_uppercase()))
+        .filter(|w| seen.insert(w.clone()))
+        .collect()
 }
 
-pub fn hints(plusword: &str, across_words: &Vec<String>) -> [[Option<Hint>; 5]; 5] {
-    let mut grid: [[Option<Hint>; 5]; 5] = [[None; 5]; 5];
+/// The hints a plusword produces on the across entries, flattened into one
+/// key. Two words with the same signature can't be told apart by a player.
+fn hint_signature(plusword: &str, across: &[(Entry, &str)]) -> Vec<Option<Hint>> {
+    across
+        .iter()
+        .flat_map(|(entry, word)| hints::score(word, plusword, entry.col))
+        .collect()
+}
 
-    for (i, word) in across_words.iter().enumerate() {
-        grid[i] = hints_across(&plusword, &word);
-    }
-    grid
+fn across_pairs<'a>(grid: &Grid, across_words: &'a [String]) -> Vec<(Entry, &'a str)> {
+    grid.entries()
+        .into_iter()
+        .filter(|entry| entry.direction == Direction::Across)
+        .zip(across_words.iter().map(String::as_str))
+        .collect()
 }
 
-fn hints_across(plusword: &str, word: &str) -> [Option<Hint>; 5] {
-    let mut hints: [Option<Hint>; 5] = [None; 5];
+/// Every word in `words` that gives the same hint grid as `plusword`, i.e.
+/// everything a player could still be looking for once the grid is solved.
+/// Includes `plusword` itself when it is in the list.
+pub fn plusword_candidates(
+    words: &[String],
+    grid: &Grid,
+    across_words: &[String],
+    plusword: &str,
+) -> Vec<String> {
+    let across = across_pairs(grid, across_words);
+    let target = hint_signature(&plusword.to_ascii_uppercase(), &across);
+    plusword_pool(words, grid.width)
+        .into_iter()
+        .filter(|word| hint_signature(word, &across) == target)
+        .collect()
+}
 
-    let solution_chars: Vec<char> = plusword.chars().collect();
-    let word_chars: Vec<char> = word.chars().collect();
+/// Picks a plusword whose hints leave at most `max_candidates` possible
+/// answers among `words`, choosing at random among those that qualify,
+/// weighted towards familiar words when there's frequency data.
+/// Returns `None` when no word is deducible enough for these across words.
+///
+/// Words are bucketed by hint signature, so this is one hint computation
+/// per word rather than one per pair.
+pub fn choose_plusword<R: rand::Rng>(
+    words: &[String],
+    grid: &Grid,
+    across_words: &[String],
+    max_candidates: usize,
+    commonness: &HashMap<String, f64>,
+    rng: &mut R,
+) -> Option<String> {
+    use rand::seq::SliceRandom;
 
-    // 1. GREEN pass - check position match
-    for i in 0..word_chars.len().min(solution_chars.len()) {
-        if word_chars[i].eq_ignore_ascii_case(&solution_chars[i]) {
-            hints[i] = Some(Hint::Green);
-        }
+    let across = across_pairs(grid, across_words);
+    let pool = plusword_pool(words, grid.width);
+    let signatures: Vec<Vec<Option<Hint>>> = pool
+        .iter()
+        .map(|word| hint_signature(word, &across))
+        .collect();
+
+    let mut bucket_sizes: HashMap<&[Option<Hint>], usize> = HashMap::new();
+    for signature in &signatures {
+        *bucket_sizes.entry(signature).or_insert(0) += 1;
+    }
+
+    let acceptable: Vec<&String> = pool
+        .iter()
+        .zip(&signatures)
+        .filter(|(_, signature)| bucket_sizes[signature.as_slice()] <= max_candidates)
+        .map(|(word, _)| word)
+        .collect();
+
+    // Without frequency data, pick exactly as before it existed, so stored
+    // seeds still reproduce their puzzles.
+    if commonness.is_empty() {
+        return acceptable.choose(rng).map(|word| (*word).clone());
+    }
+    acceptable
+        .choose_weighted(rng, |word| familiarity(commonness, word))
+        .ok()
+        .map(|word| (*word).clone())
+}
+
+/// Derives the generation seed for a date. Mixing in a secret keeps
+/// upcoming puzzles from being predicted by anyone who has the word lists.
+///
+/// FNV-1a rather than `DefaultHasher`, whose output may change between
+/// Rust releases.
+pub fn seed_for_date(date: &str, secret: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in secret.bytes().chain([b':']).chain(date.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
     }
-    // 2. YELLOW pass - check if char exists anywhere in plusword (not already GREEN)
-    for i in 0..word_chars.len() {
-        if hints[i].is_none() && solution_chars.contains(&word_chars[i]) {
-            hints[i] = Some(Hint::Yellow);
+    hash
+}
+
+/// The random source for picking a plusword after filling with `seed`. It
+/// runs on its own stream so it doesn't depend on how much randomness the
+/// fill used.
+pub fn plusword_rng(seed: u64) -> rand_chacha::ChaCha8Rng {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+    rng
+}
+
+/// Hint colours for every cell of `grid`, comparing each across entry with
+/// the plusword letters in the same columns. Blocks and cells only crossed
+/// by a down entry get no hint. Fails if a word doesn't fit its entry.
+pub fn hints(
+    plusword: &str,
+    grid: &Grid,
+    across_words: &[String],
+) -> Result<Vec<Vec<Option<Hint>>>, String> {
+    let mut hints = vec![vec![None; grid.width]; grid.height];
+
+    let across_entries = grid
+        .entries()
+        .into_iter()
+        .filter(|entry| entry.direction == Direction::Across);
+    for (entry, word) in across_entries.zip(across_words) {
+        let row_hints = hints::score(word, plusword, entry.col);
+        if row_hints.len() != entry.length {
+            return Err(format!(
+                "{} has {} letters but {} across needs {}",
+                word,
+                row_hints.len(),
+                entry.number,
+                entry.length
+            ));
         }
+        hints[entry.row][entry.col..entry.col + entry.length].copy_from_slice(&row_hints);
     }
-    hints
+    Ok(hints)
 }
 
-pub fn generate_crossword(clue_words: &[ClueWord]) -> Option<(Vec<ClueWord>, Vec<ClueWord>)> {
+/// Fills `grid` from `clue_words`, returning the across words and down
+/// words in `Grid::entries` order, with one clue each, picked by `selector`
+/// from every clue for that word. Words that `commonness` (upper-cased word
+/// to 0..=1) marks as familiar are tried earlier.
+///
+/// The result depends only on `seed` and the order of `clue_words`, as long
+/// as the search finishes within the budget's node limit; the time limit is
+/// only a safety net and can make a slow machine give up where a fast one
+/// wouldn't.
+pub fn generate_crossword(
+    clue_words: &[ClueWord],
+    grid: &Grid,
+    seed: u64,
+    selector: &ClueSelector,
+    commonness: &HashMap<String, f64>,
+) -> Result<(Vec<ClueWord>, Vec<ClueWord>), SolveError> {
     use rand::seq::SliceRandom;
-    use rand::thread_rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
 
     let start_time = Instant::now();
-    println!("Starting crossword generation...");
+    log::debug!("Starting crossword generation (seed {:016x})", seed);
 
-    let mut rng = thread_rng();
-    let mut shuffled = clue_words.to_vec();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut shuffled: Vec<&ClueWord> = clue_words.iter().collect();
     shuffled.shuffle(&mut rng);
-    let n = shuffled.len();
 
-    let mut grid: [[char; 5]; 5] = [['-'; 5]; 5];
+    // Divide each word's place in the shuffle by its familiarity, moving
+    // common words towards the front without fixing their order. When no
+    // word has a frequency, every weight is the same and the shuffle
+    // stands.
+    let mut keyed: Vec<(f64, &ClueWord)> = shuffled
+        .into_iter()
+        .enumerate()
+        .map(|(i, cw)| ((i + 1) as f64 / familiarity(commonness, &cw.word), cw))
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let shuffled: Vec<&ClueWord> = keyed.into_iter().map(|(_, cw)| cw).collect();
 
-    let mut prefix_set: HashSet<String> = HashSet::new();
+    // Every clue for each word, in shuffled order so ties between equally
+    // good clues are broken by the seed.
+    let mut clues_for_word: HashMap<String, Vec<&ClueWord>> = HashMap::new();
     for clue_word in &shuffled {
-        for i in 1..6 {
-            prefix_set.insert(clue_word.word[..i].to_string());
-        }
+        clues_for_word
+            .entry(clue_word.word.to_uppercase())
+            .or_default()
+            .push(clue_word);
     }
-    let mut index0: usize = 0;
-    let mut index1: usize = 0;
-    let mut index2: usize = 0;
-    let mut index3: usize = 0;
-    let mut index4: usize = 0;
-
-    'outer: for i0 in 0..n {
-        index0 = i0;
-        set_across(&mut grid, 0, &shuffled[i0].word);
-        for i1 in 0..n {
-            index1 = i1;
-            set_across(&mut grid, 1, &shuffled[i1].word);
-            if !test(&prefix_set, grid) {
-                continue;
-            }
-            for i2 in 0..n {
-                index2 = i2;
-                set_across(&mut grid, 2, &shuffled[i2].word);
-                if !test(&prefix_set, grid) {
-                    continue;
-                }
-                for i3 in 0..n {
-                    index3 = i3;
-                    set_across(&mut grid, 3, &shuffled[i3].word);
-                    if !test(&prefix_set, grid) {
-                        continue;
-                    }
-                    for i4 in 0..n {
-                        index4 = i4;
-                        set_across(&mut grid, 4, &shuffled[i4].word);
-                        if !test(&prefix_set, grid) {
-                            continue;
-                        } else {
-                            break 'outer;
-                        }
-                    }
-                    set_across(&mut grid, 4, "-----");
-                }
-                set_across(&mut grid, 3, "-----");
-            }
-            set_across(&mut grid, 2, "-----");
-        }
-        set_across(&mut grid, 1, "-----");
-    }
-    dbg_print_grid(grid);
-    let across_words = vec![
-        shuffled[index0].clone(),
-        shuffled[index1].clone(),
-        shuffled[index2].clone(),
-        shuffled[index3].clone(),
-        shuffled[index4].clone(),
-    ];
-    let down_words = vec![
-        down_word_at_i(&grid, 0).to_uppercase(),
-        down_word_at_i(&grid, 1).to_uppercase(),
-        down_word_at_i(&grid, 2).to_uppercase(),
-        down_word_at_i(&grid, 3).to_uppercase(),
-        down_word_at_i(&grid, 4).to_uppercase(),
-    ]
-    .iter()
-    .map(|w| {
-        shuffled
-            .iter()
-            .find(|clue_word| clue_word.word.eq(w))
-            .unwrap()
-            .clone()
-    })
-    .collect::<Vec<ClueWord>>();
 
+    let index = WordIndex::new(shuffled.iter().map(|cw| cw.word.as_str()));
+    let entries = grid.entries();
+    let slots: Vec<Slot> = entries.iter().map(Entry::slot).collect();
+
+    let result = solver::solve(&index, &slots, grid.width, grid.height, Budget::default());
     let elapsed = start_time.elapsed();
-    eprintln!("Generated crossword in ({:.2}s)", elapsed.as_secs_f64());
-    Some((across_words, down_words))
-}
 
-fn down_word_at_i(grid: &[[char; 5]; 5], i: usize) -> String {
-    vec![grid[0][i], grid[1][i], grid[2][i], grid[3][i], grid[4][i]]
+    let fill = match result {
+        Ok(fill) => fill,
+        Err(e) => {
+            log::debug!(
+                "Crossword generation failed after {:.2}s: {}",
+                elapsed.as_secs_f64(),
+                e
+            );
+            return Err(e);
+        }
+    };
+
+    let words: Vec<ClueWord> = fill
+        .iter()
+        .map(|&i| {
+            let candidates = &clues_for_word[index.word(i)];
+            selector
+                .choose(candidates)
+                .expect("every word in the index has a clue")
+                .clone()
+        })
+        .collect();
+    let across_count = entries
         .iter()
-        .collect::<String>()
+        .filter(|entry| entry.direction == Direction::Across)
+        .count();
+    let (across_words, down_words) = words.split_at(across_count);
+
+    if log::log_enabled!(log::Level::Debug) {
+        log_grid(grid, across_words, down_words);
+    }
+    log::debug!("Generated crossword in {:.2}s", elapsed.as_secs_f64());
+    Ok((across_words.to_vec(), down_words.to_vec()))
 }
 
-fn test(prefix_set: &HashSet<String>, grid: [[char; 5]; 5]) -> bool {
-    let mut depth: usize = 0;
-    for i in 0..5 {
-        if grid[i][0] != '-' {
-            depth += 1;
-        } else {
-            break;
+/// Logs a filled grid at debug level, one row per line.
+fn log_grid(grid: &Grid, across_words: &[ClueWord], down_words: &[ClueWord]) {
+    let mut cells = vec![vec!['#'; grid.width]; grid.height];
+    for (entry, clue_word) in grid
+        .entries()
+        .iter()
+        .zip(across_words.iter().chain(down_words))
+    {
+        for ((row, col), letter) in entry.cells().into_iter().zip(clue_word.word.chars()) {
+            cells[row][col] = letter;
         }
     }
-    let mut used_words = vec![];
-    if depth == 5 {
-        for i in 0..5 {
-            used_words.push(
-                format!(
-                    "{}{}{}{}{}",
-                    grid[i][0], grid[i][1], grid[i][2], grid[i][3], grid[i][4]
-                )
-                .to_string(),
-            );
-        }
+    for row in cells {
+        log::debug!("{}", row.into_iter().collect::<String>());
     }
+}
 
-    for j in 0..5 {
-        let mut down: [char; 5] = ['-'; 5];
-        for i in 0..depth {
-            down[i] = grid[i][j];
-        }
-        let prefix: String = down[..depth].iter().collect();
-        if !prefix_set.contains(&prefix) {
-            return false;
-        }
-        if used_words.contains(&prefix) {
-            return false;
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    true
-}
+    #[test]
+    fn hints_reject_a_word_that_overruns_its_entry() {
+        let across = ["HEARTS", "EMBER", "ABUSE", "RESIN", "TREND"].map(str::to_owned);
+        let error = hints("STARE", &Grid::daily(), &across).unwrap_err();
+        assert_eq!(error, "HEARTS has 6 letters but 1 across needs 5");
 
-fn dbg_print_grid(grid: [[char; 5]; 5]) {
-    for i in 0..5 {
-        println!(
-            "{}{}{}{}{}",
-            grid[i][0], grid[i][1], grid[i][2], grid[i][3], grid[i][4]
-        );
+        let across = across.map(|word| word[..5].to_owned());
+        assert!(hints("STARE", &Grid::daily(), &across).is_ok());
     }
 }
-
-fn set_across(grid: &mut [[char; 5]; 5], i: usize, word: &str) {
-    grid[i][0] = word.chars().nth(0).unwrap();
-    grid[i][1] = word.chars().nth(1).unwrap();
-    grid[i][2] = word.chars().nth(2).unwrap();
-    grid[i][3] = word.chars().nth(3).unwrap();
-    grid[i][4] = word.chars().nth(4).unwrap();
-}
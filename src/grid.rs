@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+use crate::solver::Slot;
+
+/// Shortest run of open cells that counts as an entry. Shorter runs are
+/// just checked by their crossing word.
+const MIN_ENTRY_LENGTH: usize = 2;
+
+/// Shape of a crossword: its size and which cells are black squares.
+///
+/// Stored and served as one string per row, `.` for an open cell and `#`
+/// for a block, e.g. `["..#", "...", "#.."]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    blocks: Vec<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Across,
+    Down,
+}
+
+/// A numbered slot in the grid that takes one answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub number: usize,
+    pub direction: Direction,
+    pub row: usize,
+    pub col: usize,
+    pub length: usize,
+}
+
+impl Entry {
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        (0..self.length)
+            .map(|i| match self.direction {
+                Direction::Across => (self.row, self.col + i),
+                Direction::Down => (self.row + i, self.col),
+            })
+            .collect()
+    }
+
+    pub fn slot(&self) -> Slot {
+        Slot {
+            cells: self.cells(),
+        }
+    }
+}
+
+impl Grid {
+    /// A `width` x `height` grid with no black squares.
+    pub fn full(width: usize, height: usize) -> Self {
+        Grid {
+            width,
+            height,
+            blocks: vec![false; width * height],
+        }
+    }
+
+    /// The shape of the regular daily puzzle.
+    pub fn daily() -> Self {
+        Grid::full(5, 5)
+    }
+
+    /// Looks up one of the built-in shapes by name.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "daily" | "5x5" => Some(Grid::daily()),
+            "mini" | "4x4" => Some(Grid::full(4, 4)),
+            "6x6" => Some(Grid::full(6, 6)),
+            "7x7" => Grid::from_rows(&[
+                "...#...", "...#...", ".......", "###.###", ".......", "...#...", "...#...",
+            ])
+            .ok(),
+            _ => None,
+        }
+    }
+
+    /// Parses a grid from rows of `.` (open) and `#` (block).
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Result<Self, String> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.as_ref().chars().count());
+        if width == 0 || height == 0 {
+            return Err("grid must have at least one row and column".to_string());
+        }
+
+        let mut blocks = Vec::with_capacity(width * height);
+        for (i, row) in rows.iter().enumerate() {
+            let row = row.as_ref();
+            if row.chars().count() != width {
+                return Err(format!(
+                    "grid row {} has length {}, expected {}",
+                    i,
+                    row.chars().count(),
+                    width
+                ));
+            }
+            for c in row.chars() {
+                match c {
+                    '.' => blocks.push(false),
+                    '#' => blocks.push(true),
+                    other => return Err(format!("unexpected character '{}' in grid", other)),
+                }
+            }
+        }
+
+        Ok(Grid {
+            width,
+            height,
+            blocks,
+        })
+    }
+
+    pub fn to_rows(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|row| {
+                (0..self.width)
+                    .map(|col| if self.is_block(row, col) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn is_block(&self, row: usize, col: usize) -> bool {
+        self.blocks[row * self.width + col]
+    }
+
+    fn is_open(&self, row: usize, col: usize) -> bool {
+        row < self.height && col < self.width && !self.is_block(row, col)
+    }
+
+    /// All entries, numbered the usual way: reading left to right, top to
+    /// bottom, each cell that starts an across or down entry gets the next
+    /// number. Across entries come first, then down, each in number order.
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut across = Vec::new();
+        let mut down = Vec::new();
+        let mut number = 0;
+
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if !self.is_open(row, col) {
+                    continue;
+                }
+
+                let across_len = self.run_length(row, col, Direction::Across);
+                let down_len = self.run_length(row, col, Direction::Down);
+                let starts_across =
+                    (col == 0 || !self.is_open(row, col - 1)) && across_len >= MIN_ENTRY_LENGTH;
+                let starts_down =
+                    (row == 0 || !self.is_open(row - 1, col)) && down_len >= MIN_ENTRY_LENGTH;
+
+                if starts_across || starts_down {
+                    number += 1;
+                }
+                if starts_across {
+                    across.push(Entry {
+                        number,
+                        direction: Direction::Across,
+                        row,
+                        col,
+                        length: across_len,
+                    });
+                }
+                if starts_down {
+                    down.push(Entry {
+                        number,
+                        direction: Direction::Down,
+                        row,
+                        col,
+                        length: down_len,
+                    });
+                }
+            }
+        }
+
+        across.extend(down);
+        across
+    }
+
+    fn run_length(&self, row: usize, col: usize, direction: Direction) -> usize {
+        let mut length = 0;
+        loop {
+            let (r, c) = match direction {
+                Direction::Across => (row, col + length),
+                Direction::Down => (row + length, col),
+            };
+            if !self.is_open(r, c) {
+                return length;
+            }
+            length += 1;
+        }
+    }
+}
+
+impl Serialize for Grid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_rows().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<String>::deserialize(deserializer)?;
+        Grid::from_rows(&rows).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::models::Hint;
+
+/// Scores `guess` against `solution` the way Wordle does.
+///
+/// A letter in the right place is green. Otherwise it's yellow if the
+/// solution has an occurrence of that letter left over after the greens and
+/// any earlier yellows have claimed theirs, so a solution with one E turns
+/// at most one misplaced E yellow. Comparison ignores case.
+///
+/// `offset` is the position in `solution` of the guess's first letter,
+/// for guesses shorter than the solution (an across entry that doesn't
+/// start in the first column). Letters beyond the solution can only be
+/// yellow.
+pub fn score(guess: &str, solution: &str, offset: usize) -> Vec<Option<Hint>> {
+    let guess: Vec<char> = guess.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let solution: Vec<char> = solution.chars().map(|c| c.to_ascii_uppercase()).collect();
+
+    let mut hints: Vec<Option<Hint>> = vec![None; guess.len()];
+    let mut matched = vec![false; solution.len()];
+
+    // 1. GREEN pass - letters in the right place
+    for (i, letter) in guess.iter().enumerate() {
+        if solution.get(offset + i) == Some(letter) {
+            hints[i] = Some(Hint::Green);
+            matched[offset + i] = true;
+        }
+    }
+
+    // 2. YELLOW pass - each unmatched solution letter can turn one guess
+    // letter yellow, leftmost first
+    let mut remaining: HashMap<char, usize> = HashMap::new();
+    for (letter, _) in solution.iter().zip(&matched).filter(|(_, m)| !**m) {
+        *remaining.entry(*letter).or_default() += 1;
+    }
+    for (i, letter) in guess.iter().enumerate() {
+        if hints[i].is_some() {
+            continue;
+        }
+        if let Some(count) = remaining.get_mut(letter).filter(|count| **count > 0) {
+            *count -= 1;
+            hints[i] = Some(Hint::Yellow);
+        }
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Hint::{Green, Yellow};
+
+    /// Scores the slow, obvious way: a misplaced letter is yellow if the
+    /// solution has more of it than the greens and earlier yellows use.
+    fn reference(guess: &str, solution: &str, offset: usize) -> Vec<Option<Hint>> {
+        let guess: Vec<char> = guess.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let solution: Vec<char> = solution.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let green = |i: usize| solution.get(offset + i) == Some(&guess[i]);
+
+        let mut hints = Vec::new();
+        for i in 0..guess.len() {
+            if green(i) {
+                hints.push(Some(Green));
+                continue;
+            }
+            let letter = guess[i];
+            let in_solution = solution.iter().filter(|&&c| c == letter).count();
+            let greens = (0..guess.len())
+                .filter(|&j| guess[j] == letter && green(j))
+                .count();
+            let earlier_yellows = (0..i)
+                .filter(|&j| guess[j] == letter && hints[j] == Some(Yellow))
+                .count();
+            hints.push((in_solution > greens + earlier_yellows).then_some(Yellow));
+        }
+        hints
+    }
+
+    /// Every word of `length` letters from A, B and C.
+    fn words(length: u32) -> Vec<String> {
+        (0..3usize.pow(length))
+            .map(|mut n| {
+                (0..length)
+                    .map(|_| {
+                        let letter = (b'A' + (n % 3) as u8) as char;
+                        n /= 3;
+                        letter
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_reference_for_every_five_letter_pair() {
+        let words = words(5);
+        for solution in &words {
+            for guess in &words {
+                assert_eq!(
+                    score(guess, solution, 0),
+                    reference(guess, solution, 0),
+                    "{} against {}",
+                    guess,
+                    solution
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_reference_for_every_offset_entry() {
+        let solutions = words(5);
+        let guesses = words(3);
+        for solution in &solutions {
+            for guess in &guesses {
+                // Offsets 3 and 4 run the guess off the end of the solution.
+                for offset in 0..=4 {
+                    assert_eq!(
+                        score(guess, solution, offset),
+                        reference(guess, solution, offset),
+                        "{} at {} against {}",
+                        guess,
+                        offset,
+                        solution
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_e_turns_at_most_one_misplaced_e_yellow() {
+        assert_eq!(
+            score("EEXXX", "ABCDE", 0),
+            vec![Some(Yellow), None, None, None, None]
+        );
+        // The green E claims the only E, leaving none for a yellow.
+        assert_eq!(
+            score("EXXXE", "ABCDE", 0),
+            vec![None, None, None, None, Some(Green)]
+        );
+        assert_eq!(
+            score("EEXXX", "ABEDE", 0),
+            vec![Some(Yellow), Some(Yellow), None, None, None]
+        );
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert_eq!(score("pLuSh", "PLUSH", 0), vec![Some(Green); 5]);
+        assert_eq!(score("hsulp", "Plush", 0), score("HSULP", "PLUSH", 0));
+    }
+
+    #[test]
+    fn offset_entries_line_up_with_the_solution() {
+        assert_eq!(score("DE", "ABCDE", 3), vec![Some(Green), Some(Green)]);
+        assert_eq!(score("ED", "ABCDE", 3), vec![Some(Yellow), Some(Yellow)]);
+        // Letters past the end of the solution can still be yellow.
+        assert_eq!(
+            score("EAB", "ABCDE", 4),
+            vec![Some(Green), Some(Yellow), Some(Yellow)]
+        );
+        assert_eq!(score("EZ", "ABCDE", 4), vec![Some(Green), None]);
+    }
+}
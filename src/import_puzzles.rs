@@ -1,191 +1,667 @@
-use rusqlite::{Connection, Result};
-use serde::{Deserialize, Serialize};
+//! `quinta import-puzzles`: loads puzzles fetched by `fetch_puzzles` into
+//! the database, checking each one makes a valid grid and warning when its
+//! plusword isn't deducible the way a generated one would be.
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Result, Transaction};
+use std::collections::BTreeSet;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Serialize)]
-struct FetchedClue {
-    clue: String,
-    slug: String,
+use crate::error::{self, Error};
+use crate::fetch_puzzles::{FetchedClue, FetchedPuzzle};
+use crate::grid::Grid;
+use crate::{config, generator, migrations, moderation};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Fetched puzzles, as written by fetch-puzzles
+    #[arg(default_value = "puzzles.json")]
+    file: PathBuf,
+
+    /// Report what would change without writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// One entry's answer and clue, as fetched or as already stored.
+#[derive(Debug, PartialEq)]
+struct Entry {
     word: String,
-    dir: String,
-    num: i32,
-}
-
-#[derive(Debug, Deserialize)]
-struct FetchedPuzzle {
-    date: String,
-    clues: Vec<FetchedClue>,
-}
-
-fn init_database(conn: &Connection) -> Result<()> {
-    // Table for word-clue pairs
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS clue_word_pairs (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            word TEXT NOT NULL,
-            clue TEXT NOT NULL,
-            slug TEXT NOT NULL,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-            UNIQUE(word, clue)
-        )",
-        [],
-    )?;
+    clue: String,
+}
+
+/// A puzzle's contents, for comparing what's fetched with what's stored.
+#[derive(Debug, PartialEq)]
+struct Contents {
+    plusword: String,
+    across: Vec<Entry>,
+    down: Vec<Entry>,
+}
+
+/// The puzzle already stored for a date.
+struct Stored {
+    id: i64,
+    /// Built by the generator rather than imported; never overwritten.
+    generated: bool,
+    contents: Contents,
+}
+
+/// What an import did, or with `--dry-run` would have done.
+#[derive(Default)]
+struct Report {
+    added: Vec<String>,
+    updated: Vec<(String, Vec<String>)>,
+    unchanged: usize,
+    conflicts: Vec<(String, String)>,
+    quarantined: Vec<(String, String)>,
+    skipped: Vec<(String, String)>,
+    warnings: Vec<(String, String)>,
+    clues_added: usize,
+    clue_conflicts: BTreeSet<String>,
+}
+
+/// The width and height of a Plusword grid, which has no black squares.
+const SIZE: usize = 5;
+
+/// The row of the across entry numbered `num`. In a grid without black
+/// squares the top row holds 1 to SIZE (1 across and every down entry), and
+/// the first cell of each later row continues from there.
+fn across_row(num: i32) -> Option<usize> {
+    match usize::try_from(num).ok()? {
+        1 => Some(0),
+        n if n > SIZE && n < 2 * SIZE => Some(n - SIZE),
+        _ => None,
+    }
+}
+
+/// The column of the down entry numbered `num`.
+fn down_column(num: i32) -> Option<usize> {
+    match usize::try_from(num).ok()? {
+        n if (1..=SIZE).contains(&n) => Some(n - 1),
+        _ => None,
+    }
+}
+
+/// A fetched puzzle laid out as a grid: the across entries by row, the
+/// down entries by column, and the plusword.
+struct Parsed<'a> {
+    plusword: &'a FetchedClue,
+    across: Vec<&'a FetchedClue>,
+    down: Vec<&'a FetchedClue>,
+}
+
+/// Puts each clue in its place from its number and direction, or says why
+/// the puzzle doesn't make a valid grid.
+///
+/// The plusword is the across clue that mentions it, or else the one whose
+/// number isn't an across entry. Every crossing must agree, and the
+/// plusword must get at least one hint from the rows.
+fn parse_puzzle(puzzle: &FetchedPuzzle) -> Result<Parsed<'_>, String> {
+    let mut plusword = Vec::new();
+    let mut across = vec![None; SIZE];
+    let mut down = vec![None; SIZE];
+
+    for clue in &puzzle.clues {
+        let (slots, slot) = match clue.dir.as_str() {
+            "across" => {
+                let row = across_row(clue.num);
+                if row.is_none() || clue.clue.to_lowercase().contains("plusword") {
+                    plusword.push(clue);
+                    continue;
+                }
+                (&mut across, row)
+            }
+            "down" => (&mut down, down_column(clue.num)),
+            other => return Err(format!("{} {} has an unknown direction", clue.num, other)),
+        };
+        let Some(slot) = slot else {
+            return Err(format!(
+                "{} {} isn't an entry in a {}x{} grid",
+                clue.num, clue.dir, SIZE, SIZE
+            ));
+        };
+        if slots[slot].replace(clue).is_some() {
+            return Err(format!("more than one clue for {} {}", clue.num, clue.dir));
+        }
+    }
+
+    let plusword = match plusword.as_slice() {
+        [clue] => *clue,
+        [] => return Err("no plusword clue".to_owned()),
+        clues => {
+            let nums: Vec<String> = clues.iter().map(|c| c.num.to_string()).collect();
+            return Err(format!(
+                "more than one plusword candidate ({} across)",
+                nums.join(", ")
+            ));
+        }
+    };
+
+    if let Some(row) = across.iter().position(Option::is_none) {
+        let num = if row == 0 { 1 } else { SIZE + row };
+        return Err(format!("no clue for {} across", num));
+    }
+    if let Some(column) = down.iter().position(Option::is_none) {
+        return Err(format!("no clue for {} down", column + 1));
+    }
+    let across: Vec<&FetchedClue> = across.into_iter().flatten().collect();
+    let down: Vec<&FetchedClue> = down.into_iter().flatten().collect();
+
+    for clue in across.iter().chain(&down).chain([&plusword]) {
+        if clue.word.chars().count() != SIZE || !clue.word.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return Err(format!(
+                "{} {} \"{}\" isn't a {}-letter word",
+                clue.num, clue.dir, clue.word, SIZE
+            ));
+        }
+    }
+
+    let letter = |clue: &FetchedClue, i: usize| clue.word.as_bytes()[i].to_ascii_uppercase();
+    for (row, across_clue) in across.iter().enumerate() {
+        for (column, down_clue) in down.iter().enumerate() {
+            let (a, d) = (letter(across_clue, column), letter(down_clue, row));
+            if a != d {
+                return Err(format!(
+                    "{} across has {} where {} down has {}",
+                    across_clue.num, a as char, down_clue.num, d as char
+                ));
+            }
+        }
+    }
+
+    // The rows are scored against the plusword the way Wordle scores a
+    // guess, so a plusword sharing no letter with the grid gets no hints
+    // at all and couldn't be solved.
+    let plusword_letters = plusword.word.to_ascii_uppercase();
+    let hinted = across.iter().any(|c| {
+        c.word
+            .to_ascii_uppercase()
+            .bytes()
+            .any(|l| plusword_letters.as_bytes().contains(&l))
+    });
+    if !hinted {
+        return Err(format!(
+            "plusword {} shares no letters with the grid, so has no hints",
+            plusword.word
+        ));
+    }
+
+    Ok(Parsed {
+        plusword,
+        across,
+        down,
+    })
+}
+
+/// Why the plusword's hints don't single it out among `words` as the
+/// generator requires, if they don't: it must be one of the words its hints
+/// leave open, and they may leave at most `max_candidates`.
+fn undeducible(parsed: &Parsed, words: &[String], max_candidates: usize) -> Option<String> {
+    let across: Vec<String> = parsed.across.iter().map(|c| c.word.clone()).collect();
+    let plusword = parsed.plusword.word.to_ascii_uppercase();
+    let candidates = generator::plusword_candidates(words, &Grid::daily(), &across, &plusword);
+    if !candidates.contains(&plusword) {
+        return Some(format!("plusword {} isn't in the word list", plusword));
+    }
+    if candidates.len() > max_candidates {
+        return Some(format!(
+            "plusword {}'s hints leave {} candidates, more than {}",
+            plusword,
+            candidates.len(),
+            max_candidates
+        ));
+    }
+    None
+}
+
+/// The first of the puzzle's words and clues on the blocklist.
+fn blocked(parsed: &Parsed, blocklist: &moderation::Blocklist) -> Option<String> {
+    std::iter::once(parsed.plusword.word.as_str())
+        .chain(
+            parsed
+                .across
+                .iter()
+                .chain(&parsed.down)
+                .flat_map(|c| [c.word.as_str(), c.clue.as_str()]),
+        )
+        .find_map(|text| {
+            blocklist
+                .check(text)
+                .map(|blocked| format!("\"{}\" matches blocklist {}", text, blocked))
+        })
+}
 
-    // Table for complete puzzles - now stores word IDs instead of word strings
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS puzzles (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            date TEXT NOT NULL UNIQUE,
-            plusword TEXT NOT NULL,
-            across_word_ids TEXT NOT NULL,
-            down_word_ids TEXT NOT NULL,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        )",
-        [],
+fn load_stored(tx: &Transaction, date: &str) -> Result<Option<Stored>> {
+    let Some((id, plusword, generated)) = tx
+        .query_row(
+            "SELECT id, plusword, grid IS NOT NULL FROM puzzles WHERE date = ?1",
+            [date],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let mut contents = Contents {
+        plusword,
+        across: Vec::new(),
+        down: Vec::new(),
+    };
+    let mut stmt = tx.prepare_cached(
+        "SELECT e.direction, c.word, c.clue
+         FROM puzzle_entries e JOIN clue_word_pairs c ON c.id = e.clue_word_id
+         WHERE e.puzzle_id = ?1
+         ORDER BY e.direction, e.position",
     )?;
+    let rows = stmt.query_map([id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            Entry {
+                word: row.get(1)?,
+                clue: row.get(2)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (direction, entry) = row?;
+        match direction.as_str() {
+            "across" => contents.across.push(entry),
+            _ => contents.down.push(entry),
+        }
+    }
 
-    Ok(())
+    Ok(Some(Stored {
+        id,
+        generated,
+        contents,
+    }))
 }
 
-fn extract_plusword(clues: &[FetchedClue]) -> Option<String> {
-    clues
-        .iter()
-        .find(|c| c.clue.to_lowercase().contains("plusword") && c.clue.contains("solution"))
-        .map(|c| c.word.clone())
+/// How `new` differs from `old`, one line per changed plusword or entry.
+fn differences(old: &Contents, new: &Contents) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.plusword != new.plusword {
+        changes.push(format!("plusword: {} -> {}", old.plusword, new.plusword));
+    }
+    for (direction, old, new) in [
+        ("across", &old.across, &new.across),
+        ("down", &old.down, &new.down),
+    ] {
+        for position in 0..old.len().max(new.len()) {
+            let describe = |entry: Option<&Entry>| match entry {
+                Some(entry) => format!("{} \"{}\"", entry.word, entry.clue),
+                None => "(none)".to_owned(),
+            };
+            let (before, after) = (old.get(position), new.get(position));
+            if before != after {
+                changes.push(format!(
+                    "{} {}: {} -> {}",
+                    direction,
+                    position + 1,
+                    describe(before),
+                    describe(after)
+                ));
+            }
+        }
+    }
+    changes
 }
 
-fn insert_clue_word_pair(conn: &Connection, word: &str, clue: &str, slug: &str) -> Result<i64> {
-    conn.execute(
+/// The id of the clue-word pair, adding it if it's new. A pair already
+/// stored under a different slug keeps its slug and is reported.
+fn insert_clue_word_pair(tx: &Transaction, clue: &FetchedClue, report: &mut Report) -> Result<i64> {
+    let added = tx.execute(
         "INSERT OR IGNORE INTO clue_word_pairs (word, clue, slug) VALUES (?1, ?2, ?3)",
-        [word, clue, slug],
-    )?;
+        [&clue.word, &clue.clue, &clue.slug],
+    )? > 0;
 
-    // Get the ID of the inserted or existing row
-    let id: i64 = conn.query_row(
-        "SELECT id FROM clue_word_pairs WHERE word = ?1 AND clue = ?2",
-        [word, clue],
-        |row| row.get(0),
+    let (id, slug): (i64, String) = tx.query_row(
+        "SELECT id, slug FROM clue_word_pairs WHERE word = ?1 AND clue = ?2",
+        [&clue.word, &clue.clue],
+        |row| Ok((row.get(0)?, row.get(1)?)),
     )?;
 
+    if added {
+        report.clues_added += 1;
+    } else if slug != clue.slug {
+        report.clue_conflicts.insert(format!(
+            "{} \"{}\": stored slug {}, fetched {}",
+            clue.word, clue.clue, slug, clue.slug
+        ));
+    }
     Ok(id)
 }
 
-fn insert_puzzle(
-    conn: &Connection,
-    date: &str,
-    plusword: &str,
+/// Points the puzzle's entries at `across_word_ids` and `down_word_ids`.
+fn set_entries(
+    tx: &Transaction,
+    puzzle_id: i64,
     across_word_ids: &[i64],
     down_word_ids: &[i64],
 ) -> Result<()> {
-    let across_json = serde_json::to_string(across_word_ids).unwrap();
-    let down_json = serde_json::to_string(down_word_ids).unwrap();
+    tx.execute(
+        "DELETE FROM puzzle_entries WHERE puzzle_id = ?1",
+        [puzzle_id],
+    )?;
+    for (direction, ids) in [("across", across_word_ids), ("down", down_word_ids)] {
+        for (position, id) in ids.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO puzzle_entries (puzzle_id, direction, position, clue_word_id)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (puzzle_id, direction, position, id),
+            )?;
+        }
+    }
+    Ok(())
+}
 
-    conn.execute(
-        "INSERT OR REPLACE INTO puzzles (date, plusword, across_word_ids, down_word_ids) VALUES (?1, ?2, ?3, ?4)",
-        [date, plusword, &across_json, &down_json],
+/// Sets a malformed puzzle aside, replacing any earlier attempt at the
+/// same date.
+fn quarantine(tx: &Transaction, puzzle: &FetchedPuzzle, reason: &str) -> Result<()> {
+    let clues = serde_json::to_string(&puzzle.clues).expect("clues serialize");
+    tx.execute(
+        "INSERT INTO quarantined_puzzles (date, reason, clues) VALUES (?1, ?2, ?3)
+         ON CONFLICT(date) DO UPDATE SET
+             reason = excluded.reason, clues = excluded.clues,
+             quarantined_at = CURRENT_TIMESTAMP",
+        [&puzzle.date, reason, &clues],
     )?;
     Ok(())
 }
 
-fn main() -> Result<()> {
+fn import(
+    tx: &Transaction,
+    puzzles: &[FetchedPuzzle],
+    blocklist: &moderation::Blocklist,
+) -> Result<Report> {
+    let mut report = Report::default();
+    let words: Vec<String> = tx
+        .prepare("SELECT word FROM word")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+    let max_candidates = config::get().generation.max_plusword_candidates;
+
+    for puzzle in puzzles {
+        let parsed = match parse_puzzle(puzzle) {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                quarantine(tx, puzzle, &reason)?;
+                report.quarantined.push((puzzle.date.clone(), reason));
+                continue;
+            }
+        };
+        tx.execute(
+            "DELETE FROM quarantined_puzzles WHERE date = ?1",
+            [&puzzle.date],
+        )?;
+        if let Some(reason) = blocked(&parsed, blocklist) {
+            report.skipped.push((puzzle.date.clone(), reason));
+            continue;
+        }
+        // Without a word list there's nothing to check the plusword against.
+        let warning = if words.is_empty() {
+            None
+        } else {
+            undeducible(&parsed, &words, max_candidates)
+        };
+        let Parsed {
+            plusword,
+            across,
+            down,
+        } = parsed;
+        let plusword = plusword.word.clone();
+
+        let contents = Contents {
+            plusword: plusword.clone(),
+            across: across
+                .iter()
+                .map(|c| Entry {
+                    word: c.word.clone(),
+                    clue: c.clue.clone(),
+                })
+                .collect(),
+            down: down
+                .iter()
+                .map(|c| Entry {
+                    word: c.word.clone(),
+                    clue: c.clue.clone(),
+                })
+                .collect(),
+        };
+
+        let stored = load_stored(tx, &puzzle.date)?;
+        let changes = match &stored {
+            Some(stored) if stored.contents == contents => {
+                report.unchanged += 1;
+                continue;
+            }
+            Some(stored) if stored.generated => {
+                report.conflicts.push((
+                    puzzle.date.clone(),
+                    "a generated puzzle is already stored for this date".to_owned(),
+                ));
+                continue;
+            }
+            Some(stored) => Some(differences(&stored.contents, &contents)),
+            None => None,
+        };
+
+        let mut across_word_ids = Vec::new();
+        for clue in &across {
+            across_word_ids.push(insert_clue_word_pair(tx, clue, &mut report)?);
+        }
+        let mut down_word_ids = Vec::new();
+        for clue in &down {
+            down_word_ids.push(insert_clue_word_pair(tx, clue, &mut report)?);
+        }
+
+        // Updating in place keeps the puzzle's id, and with it its number.
+        let puzzle_id = match &stored {
+            Some(stored) => {
+                tx.execute(
+                    "UPDATE puzzles SET plusword = ?1 WHERE id = ?2",
+                    (&plusword, stored.id),
+                )?;
+                stored.id
+            }
+            None => tx.query_row(
+                "INSERT INTO puzzles (date, plusword) VALUES (?1, ?2) RETURNING id",
+                [&puzzle.date, &plusword],
+                |row| row.get(0),
+            )?,
+        };
+        set_entries(tx, puzzle_id, &across_word_ids, &down_word_ids)?;
+
+        match changes {
+            Some(changes) => report.updated.push((puzzle.date.clone(), changes)),
+            None => report.added.push(puzzle.date.clone()),
+        }
+        if let Some(warning) = warning {
+            report.warnings.push((puzzle.date.clone(), warning));
+        }
+    }
+    Ok(report)
+}
+
+fn print_report(report: &Report, total: usize) {
+    for date in &report.added {
+        println!("  + Added:    {}", date);
+    }
+    for (date, changes) in &report.updated {
+        println!("  ~ Updated:  {}", date);
+        for change in changes {
+            println!("      {}", change);
+        }
+    }
+    for (date, reason) in &report.conflicts {
+        println!("  ! Conflict: {}: {}", date, reason);
+    }
+    for (date, reason) in &report.quarantined {
+        println!("  ✗ Quarantined: {}: {}", date, reason);
+    }
+    for (date, reason) in &report.skipped {
+        println!("  ⚠ Skipped:  {}: {}", date, reason);
+    }
+    for (date, warning) in &report.warnings {
+        println!("  ⚠ Warning:  {}: {}", date, warning);
+    }
+    for conflict in &report.clue_conflicts {
+        println!("  ! Clue conflict: {}", conflict);
+    }
+
+    println!("\n======================");
+    println!("Import Summary:");
+    println!("  Added:           {}", report.added.len());
+    println!("  Updated:         {}", report.updated.len());
+    println!("  Unchanged:       {}", report.unchanged);
+    println!("  Conflicts:       {}", report.conflicts.len());
+    println!("  Quarantined:     {}", report.quarantined.len());
+    println!("  Skipped:         {}", report.skipped.len());
+    println!("  Warnings:        {}", report.warnings.len());
+    println!("  Total:           {}", total);
+    println!("  New clues:       {}", report.clues_added);
+    println!("  Clue conflicts:  {}", report.clue_conflicts.len());
+}
+
+pub fn run(db: &Path, args: Args) -> error::Result<()> {
     println!("Quinta Puzzle Importer");
     println!("=====================\n");
 
-    // Read the puzzles.json file
-    let json_content = fs::read_to_string("puzzles.json").expect("Failed to read puzzles.json");
-    let puzzles: Vec<FetchedPuzzle> =
-        serde_json::from_str(&json_content).expect("Failed to parse puzzles.json");
+    let path = args.file.display();
+    let json_content =
+        fs::read_to_string(&args.file).map_err(|e| Error::Io(format!("reading {}", path), e))?;
+    let puzzles: Vec<FetchedPuzzle> = serde_json::from_str(&json_content)
+        .map_err(|e| Error::Invalid(format!("parsing {}: {}", path, e)))?;
 
-    println!("Found {} puzzles to import\n", puzzles.len());
+    println!("Found {} puzzles in {}\n", puzzles.len(), path);
 
-    // Open database connection
-    let conn = Connection::open("quinta.db")?;
-    init_database(&conn)?;
+    let conn = open(db, args.dry_run)?;
+    Ok(import_file(conn, db, &puzzles, args.dry_run)?)
+}
 
-    let mut imported_count = 0;
-    let mut skipped_count = 0;
-    let total_count = puzzles.len();
+/// Opens the database, bringing its schema up to date, except in a dry
+/// run: that mustn't change anything, so the database has to exist and be
+/// migrated already.
+fn open(db: &Path, dry_run: bool) -> error::Result<Connection> {
+    let mut conn = match dry_run {
+        true => Connection::open_with_flags(
+            db,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        ),
+        false => Connection::open(db),
+    }?;
+    conn.pragma_update(None, "foreign_keys", true)?;
 
-    for puzzle in &puzzles {
-        println!("Processing puzzle for {}...", puzzle.date);
+    if dry_run {
+        let pending = migrations::pending(&conn)?;
+        if let Some(latest) = pending.last() {
+            let current = migrations::current_version(&conn)?;
+            return Err(Error::Schema(format!(
+                "database schema is at version {} but {} is needed; run `quinta migrate` first",
+                current, latest.version
+            )));
+        }
+    } else {
+        migrations::migrate(&mut conn, config::get().puzzle.today())?;
+    }
+    Ok(conn)
+}
 
-        // Separate across and down clues
-        let across_clues: Vec<&FetchedClue> =
-            puzzle.clues.iter().filter(|c| c.dir == "across").collect();
+fn import_file(
+    mut conn: Connection,
+    db: &Path,
+    puzzles: &[FetchedPuzzle],
+    dry_run: bool,
+) -> Result<()> {
+    let blocklist = moderation::Blocklist::load(
+        &conn,
+        config::get().moderation.blocklist_categories.as_deref(),
+    )?;
+    println!(
+        "Filtering against {} blocklist pattern(s)\n",
+        blocklist.len()
+    );
 
-        let down_clues: Vec<&FetchedClue> =
-            puzzle.clues.iter().filter(|c| c.dir == "down").collect();
+    // Everything happens in one transaction, so a failure part way through
+    // leaves the database as it was.
+    let tx = conn.transaction()?;
+    let report = import(&tx, puzzles, &blocklist)?;
+    print_report(&report, puzzles.len());
 
-        // Check if we have exactly 5 across and 5 down (standard Plusword format)
-        if across_clues.len() != 6 || down_clues.len() != 5 {
-            println!(
-                "  ⚠ Skipping: Expected 6 across and 5 down, got {} across and {} down",
-                across_clues.len(),
-                down_clues.len()
-            );
-            skipped_count += 1;
-            continue;
-        }
+    if dry_run {
+        tx.rollback()?;
+        println!("\nDry run: nothing was written to {}", db.display());
+    } else {
+        tx.commit()?;
+        println!("\nDatabase saved to {}", db.display());
+    }
 
-        // Extract plusword (should be the last across clue)
-        let plusword = match extract_plusword(&puzzle.clues) {
-            Some(word) => word,
-            None => {
-                println!("  ⚠ Skipping: Could not find plusword solution");
-                skipped_count += 1;
-                continue;
-            }
-        };
+    Ok(())
+}
 
-        // Get the 5 regular across clues (excluding plusword clue)
-        let across_regular_clues: Vec<&FetchedClue> = across_clues
-            .iter()
-            .filter(|c| !c.clue.to_lowercase().contains("plusword"))
-            .copied()
-            .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if across_regular_clues.len() != 5 || down_clues.len() != 5 {
-            println!(
-                "  ⚠ Skipping: After filtering, got {} across and {} down words",
-                across_regular_clues.len(),
-                down_clues.len()
-            );
-            skipped_count += 1;
-            continue;
+    /// The word square HEART / EMBER / ABUSE / RESIN / TREND with
+    /// `plusword` as its extra across clue.
+    fn puzzle(plusword: &str) -> FetchedPuzzle {
+        let rows = ["HEART", "EMBER", "ABUSE", "RESIN", "TREND"];
+        let clue = |num, dir: &str, word: String| FetchedClue {
+            clue: format!("{} {}", num, dir),
+            slug: format!("{}-{}", num, dir),
+            word,
+            dir: dir.to_owned(),
+            num,
+        };
+        let mut clues: Vec<FetchedClue> = [1, 6, 7, 8, 9]
+            .into_iter()
+            .zip(rows)
+            .map(|(num, word)| clue(num, "across", word.to_owned()))
+            .collect();
+        for column in 0..SIZE {
+            let word = rows.iter().map(|row| row.as_bytes()[column] as char);
+            clues.push(clue(column as i32 + 1, "down", word.collect()));
         }
-
-        // Insert all clue-word pairs and collect IDs
-        let mut across_word_ids = Vec::new();
-        for clue in &across_regular_clues {
-            let id = insert_clue_word_pair(&conn, &clue.word, &clue.clue, &clue.slug)?;
-            across_word_ids.push(id);
+        clues.push(clue(10, "across", plusword.to_owned()));
+        FetchedPuzzle {
+            date: "2026-01-01".to_owned(),
+            clues,
         }
+    }
 
-        let mut down_word_ids = Vec::new();
-        for clue in &down_clues {
-            let id = insert_clue_word_pair(&conn, &clue.word, &clue.clue, &clue.slug)?;
-            down_word_ids.push(id);
-        }
-
-        // Insert the complete puzzle with word IDs
-        insert_puzzle(
-            &conn,
-            &puzzle.date,
-            &plusword,
-            &across_word_ids,
-            &down_word_ids,
-        )?;
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|word| word.to_string()).collect()
+    }
 
-        println!("  ✓ Imported: {} (plusword: {})", puzzle.date, plusword);
-        imported_count += 1;
+    #[test]
+    fn accepts_a_plusword_its_hints_single_out() {
+        let puzzle = puzzle("STARE");
+        let parsed = parse_puzzle(&puzzle).unwrap();
+        assert_eq!(
+            undeducible(&parsed, &words(&["stare", "tears", "ozone"]), 1),
+            None
+        );
     }
 
-    println!("\n======================");
-    println!("Import Summary:");
-    println!("  Imported: {}", imported_count);
-    println!("  Skipped:  {}", skipped_count);
-    println!("  Total:    {}", total_count);
-    println!("\nDatabase saved to quinta.db");
+    #[test]
+    fn warns_about_a_plusword_missing_from_the_word_list() {
+        let puzzle = puzzle("STARE");
+        let parsed = parse_puzzle(&puzzle).unwrap();
+        let warning = undeducible(&parsed, &words(&["TEARS"]), 5).unwrap();
+        assert!(warning.contains("isn't in the word list"), "{}", warning);
+    }
 
-    Ok(())
+    #[test]
+    fn warns_about_a_plusword_with_too_many_lookalikes() {
+        // Neither C nor O is in the grid, so they can't be told apart.
+        let puzzle = puzzle("STCCC");
+        let parsed = parse_puzzle(&puzzle).unwrap();
+        let list = words(&["STCCC", "STCCO", "STCOC"]);
+        assert_eq!(undeducible(&parsed, &list, 3), None);
+        let warning = undeducible(&parsed, &list, 2).unwrap();
+        assert!(warning.contains("leave 3 candidates"), "{}", warning);
+    }
 }
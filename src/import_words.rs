@@ -1,51 +1,504 @@
-use rusqlite::{Connection, Result};
+//! `quinta import-words`: loads a word list into the `word` table, which
+//! pluswords are drawn from.
+
+use rusqlite::{Connection, Result, Transaction};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-fn init_database(conn: &Connection) -> Result<()> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS word (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            word TEXT NOT NULL,
-            UNIQUE(word)
-        )",
-        [],
-    )?;
+use crate::error::{self, Error};
+use crate::{config, migrations, moderation};
 
-    Ok(())
+#[derive(clap::Args)]
+pub struct Args {
+    /// The word list
+    #[arg(default_value = "words.json")]
+    file: PathBuf,
+
+    /// The list's format; guessed from the file extension if left out
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Shortest word to keep
+    #[arg(long, default_value_t = DEFAULT_MIN_LENGTH)]
+    min_length: usize,
+
+    /// Longest word to keep
+    #[arg(long, default_value_t = DEFAULT_MAX_LENGTH)]
+    max_length: usize,
 }
 
-fn insert_word(conn: &Connection, word: &str) -> Result<()> {
-    conn.execute("INSERT INTO word (word) VALUES (?1)", [word])?;
-    Ok(())
+/// Word lengths kept by default: the widths of the grids the generator
+/// knows, from the 4x4 mini to 7x7.
+const DEFAULT_MIN_LENGTH: usize = 4;
+const DEFAULT_MAX_LENGTH: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+enum Format {
+    /// A JSON array of strings, like `words.json`.
+    Json,
+    /// One word per line; blank lines and `#` comments are ignored.
+    #[value(alias = "txt")]
+    Text,
+    /// Word, frequency and part of speech columns, in that order unless a
+    /// header row names them. Without a header, a second column that isn't
+    /// a number is the part of speech.
+    Csv,
+    Tsv,
+    /// A Hunspell `.dic` file: an entry count, then `word/FLAGS` lines with
+    /// optional morphological fields such as `po:noun`.
+    #[value(alias = "dic")]
+    Hunspell,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "txt" | "text" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "dic" | "hunspell" => Some(Format::Hunspell),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Text => "text",
+            Format::Csv => "CSV",
+            Format::Tsv => "TSV",
+            Format::Hunspell => "Hunspell",
+        })
+    }
+}
+
+/// A word as read from the list, before normalising.
+#[derive(Debug)]
+struct RawWord {
+    word: String,
+    frequency: Option<f64>,
+    part_of_speech: Option<String>,
+}
+
+/// A word ready to store.
+struct WordEntry {
+    frequency: Option<f64>,
+    commonness: Option<f64>,
+    part_of_speech: Option<String>,
+}
+
+fn parse_json(content: &str) -> Result<Vec<RawWord>, String> {
+    let words: Vec<String> = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    Ok(words
+        .into_iter()
+        .map(|word| RawWord {
+            word,
+            frequency: None,
+            part_of_speech: None,
+        })
+        .collect())
+}
+
+fn parse_text(content: &str) -> Vec<RawWord> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| RawWord {
+            word: line.to_owned(),
+            frequency: None,
+            part_of_speech: None,
+        })
+        .collect()
+}
+
+fn parse_delimited(content: &str, delimiter: char) -> Result<Vec<RawWord>, String> {
+    let split = |line: &str| -> Vec<String> {
+        line.split(delimiter)
+            .map(|field| field.trim().trim_matches('"').to_owned())
+            .collect()
+    };
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    // Columns by position, unless the first row is a header naming them.
+    let (mut word_column, mut frequency_column, mut pos_column) = (0, Some(1), Some(2));
+    if let Some((_, first)) = lines.peek() {
+        let fields = split(first);
+        let find = |names: &[&str]| {
+            fields
+                .iter()
+                .position(|field| names.contains(&field.to_lowercase().as_str()))
+        };
+        let words = find(&["word", "lemma"]);
+        let frequencies = find(&["frequency", "freq", "count"]);
+        let parts_of_speech = find(&["pos", "part_of_speech", "tag"]);
+        if words.is_some() || frequencies.is_some() || parts_of_speech.is_some() {
+            word_column = words.ok_or("the header has no word column")?;
+            frequency_column = frequencies;
+            pos_column = parts_of_speech;
+            lines.next();
+        } else if fields
+            .get(1)
+            .is_some_and(|field| field.parse::<f64>().is_err())
+        {
+            (frequency_column, pos_column) = (None, Some(1));
+        }
+    }
+
+    let mut words = Vec::new();
+    for (number, line) in lines {
+        let fields = split(line);
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| fields.get(column))
+                .filter(|field| !field.is_empty())
+        };
+        let frequency = match field(frequency_column) {
+            Some(value) => Some(
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|f| f.is_finite() && *f >= 0.0)
+                    .ok_or_else(|| format!("line {}: bad frequency {:?}", number + 1, value))?,
+            ),
+            None => None,
+        };
+        words.push(RawWord {
+            word: fields.get(word_column).cloned().unwrap_or_default(),
+            frequency,
+            part_of_speech: field(pos_column).cloned(),
+        });
+    }
+    Ok(words)
+}
+
+fn parse_hunspell(content: &str) -> Vec<RawWord> {
+    let mut lines = content.lines().peekable();
+    if lines
+        .peek()
+        .is_some_and(|line| line.trim().chars().all(|c| c.is_ascii_digit()))
+    {
+        lines.next();
+    }
+
+    lines
+        .filter(|line| !line.trim().is_empty() && !line.starts_with(['#', '\t']))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let entry = fields.next()?;
+            // The word runs up to the first unescaped '/', which starts
+            // the affix flags.
+            let mut word = String::new();
+            let mut chars = entry.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => word.extend(chars.next()),
+                    '/' => break,
+                    c => word.push(c),
+                }
+            }
+            let part_of_speech = fields
+                .find_map(|field| field.strip_prefix("po:"))
+                .map(str::to_owned);
+            Some(RawWord {
+                word,
+                frequency: None,
+                part_of_speech,
+            })
+        })
+        .collect()
+}
+
+/// The word as stored, lower-cased, or why it isn't usable.
+fn normalise(
+    raw: &str,
+    format: Format,
+    min_length: usize,
+    max_length: usize,
+) -> Result<String, &'static str> {
+    let raw = raw.trim();
+    // Hunspell dictionaries capitalise proper nouns.
+    if format == Format::Hunspell && raw.starts_with(|c: char| c.is_uppercase()) {
+        return Err("proper noun");
+    }
+    if raw.is_empty() || !raw.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("not a plain word");
+    }
+    if raw.len() < min_length {
+        return Err("too short");
+    }
+    if raw.len() > max_length {
+        return Err("too long");
+    }
+    Ok(raw.to_ascii_lowercase())
+}
+
+/// Frequencies scaled logarithmically against the most frequent word in
+/// the list, so the commonest word is 1 and a word never seen is 0.
+fn commonness(frequency: f64, max_frequency: f64) -> f64 {
+    if max_frequency <= 0.0 {
+        return 0.0;
+    }
+    (frequency.ln_1p() / max_frequency.ln_1p()).clamp(0.0, 1.0)
+}
+
+/// Ids of the words already stored, keyed by lower-cased word. Older
+/// imports kept words as the list spelled them, so "Apple" and "apple" are
+/// the same word.
+fn stored_words(tx: &Transaction) -> Result<HashMap<String, i64>> {
+    let mut stmt = tx.prepare("SELECT id, word FROM word ORDER BY id DESC")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(1)?.to_lowercase(), row.get(0)?))
+    })?;
+    // Descending ids, so the earliest row wins if there are several.
+    rows.collect()
+}
+
+/// Adds `word` or fills in what's new about the row already stored for it.
+/// Returns whether it was already stored.
+fn upsert_word(
+    tx: &Transaction,
+    stored: &HashMap<String, i64>,
+    word: &str,
+    entry: &WordEntry,
+) -> Result<bool> {
+    match stored.get(word) {
+        Some(id) => {
+            tx.execute(
+                "UPDATE word SET
+                     frequency = COALESCE(?2, frequency),
+                     commonness = COALESCE(?3, commonness),
+                     part_of_speech = COALESCE(?4, part_of_speech)
+                 WHERE id = ?1",
+                (id, entry.frequency, entry.commonness, &entry.part_of_speech),
+            )?;
+            Ok(true)
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO word (word, frequency, commonness, part_of_speech)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (
+                    word,
+                    entry.frequency,
+                    entry.commonness,
+                    &entry.part_of_speech,
+                ),
+            )?;
+            Ok(false)
+        }
+    }
 }
 
-fn main() -> Result<()> {
-    println!("Quinta Puzzle Importer");
-    println!("=====================\n");
+/// Writes `words` to the database in one transaction, so a failure leaves
+/// the word list as it was. Returns how many were added, already stored
+/// and blocked.
+fn store(db: &Path, words: &BTreeMap<String, WordEntry>) -> Result<(usize, usize, usize)> {
+    let mut conn = Connection::open(db)?;
+    migrations::migrate(&mut conn, config::get().puzzle.today())?;
+    let blocklist = moderation::Blocklist::load(
+        &conn,
+        config::get().moderation.blocklist_categories.as_deref(),
+    )?;
+    println!(
+        "Filtering against {} blocklist pattern(s)\n",
+        blocklist.len()
+    );
 
-    let json_content = fs::read_to_string("words.json").expect("Failed to read words.json");
-    let words: Vec<String> =
-        serde_json::from_str(&json_content).expect("Failed to parse words.json");
+    let (mut added, mut existing, mut blocked_count) = (0, 0, 0);
+    let tx = conn.transaction()?;
+    let stored = stored_words(&tx)?;
+    for (word, entry) in words {
+        if let Some(blocked) = blocklist.check(word) {
+            println!("  ⚠ Blocked: {} matches {}", word, blocked);
+            blocked_count += 1;
+            continue;
+        }
+        if upsert_word(&tx, &stored, word, entry)? {
+            existing += 1;
+        } else {
+            added += 1;
+        }
+    }
+    tx.commit()?;
+    Ok((added, existing, blocked_count))
+}
 
-    println!("Found {} words to import\n", words.len());
+pub fn run(db: &Path, args: Args) -> error::Result<()> {
+    let format = args
+        .format
+        .or_else(|| Format::from_path(&args.file))
+        .unwrap_or(Format::Text);
+    let (min_length, max_length) = (args.min_length, args.max_length);
+    let path = args.file.display();
 
-    // Open database connection
-    let conn = Connection::open("quinta.db")?;
-    init_database(&conn)?;
+    println!("Quinta Word Importer");
+    println!("====================\n");
 
-    let mut imported_count = 0;
-    let total_count = words.len();
+    let content =
+        fs::read_to_string(&args.file).map_err(|e| Error::Io(format!("reading {}", path), e))?;
+    let raw_words = match format {
+        Format::Json => parse_json(&content),
+        Format::Text => Ok(parse_text(&content)),
+        Format::Csv => parse_delimited(&content, ','),
+        Format::Tsv => parse_delimited(&content, '\t'),
+        Format::Hunspell => Ok(parse_hunspell(&content)),
+    }
+    .map_err(|e| Error::Invalid(format!("parsing {}: {}", path, e)))?;
+
+    println!(
+        "Found {} entries in {} ({})\n",
+        raw_words.len(),
+        path,
+        format
+    );
 
-    for word in &words {
-        insert_word(&conn, &word)?;
-        imported_count += 1;
+    // Normalise, merging words the list has more than once (say in
+    // different cases) and keeping the highest frequency.
+    let mut rejected: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut words: BTreeMap<String, WordEntry> = BTreeMap::new();
+    for raw in raw_words {
+        let word = match normalise(&raw.word, format, min_length, max_length) {
+            Ok(word) => word,
+            Err(reason) => {
+                *rejected.entry(reason).or_default() += 1;
+                continue;
+            }
+        };
+        let entry = words.entry(word).or_insert(WordEntry {
+            frequency: None,
+            commonness: None,
+            part_of_speech: None,
+        });
+        if raw.frequency > entry.frequency {
+            entry.frequency = raw.frequency;
+        }
+        if entry.part_of_speech.is_none() {
+            entry.part_of_speech = raw.part_of_speech;
+        }
     }
+    let max_frequency = words
+        .values()
+        .filter_map(|entry| entry.frequency)
+        .fold(0.0, f64::max);
+    for entry in words.values_mut() {
+        entry.commonness = entry.frequency.map(|f| commonness(f, max_frequency));
+    }
+
+    let (added_count, existing_count, blocked_count) = store(db, &words)?;
 
     println!("\n======================");
     println!("Import Summary:");
-    println!("  Imported: {}", imported_count);
-    println!("  Total:    {}", total_count);
-    println!("\nDatabase saved to quinta.db");
+    println!("  Added:          {}", added_count);
+    println!("  Already stored: {}", existing_count);
+    println!("  Blocked:        {}", blocked_count);
+    for (reason, count) in &rejected {
+        println!("  Rejected ({}): {}", reason, count);
+    }
+    println!(
+        "  With frequency: {}",
+        words.values().filter(|e| e.frequency.is_some()).count()
+    );
+    println!("\nDatabase saved to {}", db.display());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(words: &[RawWord]) -> Vec<(&str, Option<f64>, Option<&str>)> {
+        words
+            .iter()
+            .map(|raw| {
+                (
+                    raw.word.as_str(),
+                    raw.frequency,
+                    raw.part_of_speech.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_columns_a_header_names() {
+        let words = parse_delimited("pos,count,lemma\nnoun,12,apple\nverb,,run\n", ',').unwrap();
+        assert_eq!(
+            summary(&words),
+            [
+                ("apple", Some(12.0), Some("noun")),
+                ("run", None, Some("verb"))
+            ]
+        );
+
+        let error = parse_delimited("pos,count\nnoun,12\n", ',').unwrap_err();
+        assert!(error.contains("no word column"), "{}", error);
+    }
+
+    #[test]
+    fn reads_columns_by_position_without_a_header() {
+        let words = parse_delimited("apple\t12\tnoun\nrun\t3\n", '\t').unwrap();
+        assert_eq!(
+            summary(&words),
+            [
+                ("apple", Some(12.0), Some("noun")),
+                ("run", Some(3.0), None)
+            ]
+        );
+
+        let words = parse_delimited("apple,noun\nrun,verb\n", ',').unwrap();
+        assert_eq!(
+            summary(&words),
+            [("apple", None, Some("noun")), ("run", None, Some("verb"))]
+        );
+    }
+
+    #[test]
+    fn strips_hunspell_affix_flags() {
+        let dic = "4\napple/SM po:noun\nrun/SG\nAC\\/DC/M\n\tindented comment\nLondon/M\n";
+        assert_eq!(
+            summary(&parse_hunspell(dic)),
+            [
+                ("apple", None, Some("noun")),
+                ("run", None, None),
+                ("AC/DC", None, None),
+                ("London", None, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_plain_words_of_four_to_seven_letters() {
+        let keep = |raw| normalise(raw, Format::Text, DEFAULT_MIN_LENGTH, DEFAULT_MAX_LENGTH);
+        assert_eq!(keep(" Apple "), Ok("apple".to_owned()));
+        assert_eq!(keep("four"), Ok("four".to_owned()));
+        assert_eq!(keep("seventy"), Ok("seventy".to_owned()));
+        assert_eq!(keep("the"), Err("too short"));
+        assert_eq!(keep("eighteen"), Err("too long"));
+        assert_eq!(keep("can't"), Err("not a plain word"));
+        assert_eq!(
+            normalise("London", Format::Hunspell, 4, 7),
+            Err("proper noun")
+        );
+    }
+
+    #[test]
+    fn scales_frequencies_against_the_commonest_word() {
+        assert_eq!(commonness(1000.0, 1000.0), 1.0);
+        assert_eq!(commonness(0.0, 1000.0), 0.0);
+        assert_eq!(commonness(5.0, 0.0), 0.0);
+        let rare = commonness(10.0, 1000.0);
+        let common = commonness(100.0, 1000.0);
+        assert!(0.0 < rare && rare < common && common < 1.0);
+    }
+}
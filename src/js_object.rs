@@ -0,0 +1,335 @@
+//! Reads JavaScript object and array literals, as embedded in the scripts on
+//! puzzle pages, into JSON values.
+//!
+//! Covers what JSON doesn't: unquoted and single-quoted keys, single-quoted
+//! and template strings (without substitutions), JavaScript escapes,
+//! trailing commas, `undefined`, and the minifier's `!0`/`!1` for
+//! true/false. Anything that would need evaluating, like a variable, is an
+//! error rather than a guess.
+
+use serde_json::{Map, Number, Value};
+
+/// Parses the literal at the start of `source`, ignoring leading
+/// whitespace. Returns it with the number of bytes it took up; whatever
+/// follows is left alone.
+pub fn parse_prefix(source: &str) -> Result<(Value, usize), String> {
+    let mut parser = Parser { source, pos: 0 };
+    let value = parser.value()?;
+    Ok((value, parser.pos))
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> String {
+        let context: String = self.source[self.pos..].chars().take(30).collect();
+        format!("{} at byte {} (near {:?})", message, self.pos, context)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(quote @ ('"' | '\'' | '`')) => {
+                self.bump();
+                self.string(quote).map(Value::String)
+            }
+            Some('!') => {
+                self.bump();
+                match self.bump() {
+                    Some('0') => Ok(Value::Bool(true)),
+                    Some('1') => Ok(Value::Bool(false)),
+                    _ => Err(self.error("expected !0 or !1")),
+                }
+            }
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => self.number(),
+            Some(c) if is_identifier_start(c) => match self.identifier().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" | "undefined" => Ok(Value::Null),
+                "void" => {
+                    self.skip_whitespace();
+                    self.number().map(|_| Value::Null)
+                }
+                other => Err(self.error(&format!("can't evaluate '{}'", other))),
+            },
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(Value::Object(map));
+                }
+                Some(quote @ ('"' | '\'')) => {
+                    self.bump();
+                    self.string(quote)?
+                }
+                Some(c) if c.is_ascii_digit() => match self.number()? {
+                    Value::Number(n) => n.to_string(),
+                    _ => unreachable!(),
+                },
+                Some(c) if is_identifier_start(c) => self.identifier(),
+                _ => return Err(self.error("expected a key")),
+            };
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(key, value);
+
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Object(map)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    /// The rest of a string whose opening `quote` has been consumed.
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote => return Ok(text),
+                Some('$') if quote == '`' && self.peek() == Some('{') => {
+                    return Err(self.error("can't evaluate template substitution"));
+                }
+                Some('\\') => match self.bump() {
+                    None => return Err(self.error("unterminated string")),
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('v') => text.push('\u{b}'),
+                    Some('0') => text.push('\0'),
+                    // A backslash before a line break continues the string.
+                    Some('\n') => {}
+                    Some('\r') => {
+                        if self.peek() == Some('\n') {
+                            self.bump();
+                        }
+                    }
+                    Some('x') => text.push(self.hex_escape(2)?),
+                    Some('u') => text.push(self.unicode_escape()?),
+                    Some(other) => text.push(other),
+                },
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn hex_digits(&mut self, count: usize) -> Result<u32, String> {
+        let digits: String = self.source[self.pos..].chars().take(count).collect();
+        if digits.len() != count || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(self.error("bad hex escape"));
+        }
+        self.pos += count;
+        Ok(u32::from_str_radix(&digits, 16).unwrap())
+    }
+
+    fn hex_escape(&mut self, count: usize) -> Result<char, String> {
+        let code = self.hex_digits(count)?;
+        char::from_u32(code).ok_or_else(|| self.error("bad character code"))
+    }
+
+    /// `\uXXXX` (joining surrogate pairs) or `\u{X...}`, after the `u`.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        if self.peek() == Some('{') {
+            self.bump();
+            let end = self.source[self.pos..]
+                .find('}')
+                .ok_or_else(|| self.error("unterminated \\u{...}"))?;
+            let code = u32::from_str_radix(&self.source[self.pos..self.pos + end], 16)
+                .map_err(|_| self.error("bad \\u{...} escape"))?;
+            self.pos += end + 1;
+            return char::from_u32(code).ok_or_else(|| self.error("bad character code"));
+        }
+
+        let high = self.hex_digits(4)?;
+        if (0xD800..0xDC00).contains(&high) && self.source[self.pos..].starts_with("\\u") {
+            self.pos += 2;
+            let low = self.hex_digits(4)?;
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+            return char::from_u32(code).ok_or_else(|| self.error("bad surrogate pair"));
+        }
+        char::from_u32(high).ok_or_else(|| self.error("bad character code"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        if matches!(self.peek(), Some('-' | '+')) {
+            self.bump();
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-')
+        {
+            // Only allow a sign straight after an exponent marker.
+            let c = self.peek().unwrap();
+            if (c == '+' || c == '-') && !self.source[..self.pos].ends_with(['e', 'E']) {
+                break;
+            }
+            self.bump();
+        }
+        let literal = self.source[start..self.pos].trim_start_matches('+');
+
+        if let Ok(n) = literal.parse::<i64>() {
+            return Ok(Value::Number(n.into()));
+        }
+        literal
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| format!("bad number {:?} at byte {}", literal, start))
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| is_identifier_start(c) || c.is_ascii_digit())
+        {
+            self.bump();
+        }
+        self.source[start..self.pos].to_owned()
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(source: &str) -> Result<Value, String> {
+        parse_prefix(source).map(|(value, _)| value)
+    }
+
+    #[test]
+    fn reads_escapes() {
+        assert_eq!(
+            parse(r#"'a\nb\tc\'d\"e\\f\x41B\u{43}'"#).unwrap(),
+            json!("a\nb\tc'd\"e\\fABC")
+        );
+        assert_eq!(parse(r"'\uD83D\uDE00 \u{1F600}'").unwrap(), json!("😀 😀"));
+        assert_eq!(parse("'one \\\ntwo'").unwrap(), json!("one two"));
+        assert!(parse(r"'\xZZ'").is_err());
+        assert!(parse("'unterminated").is_err());
+    }
+
+    #[test]
+    fn reads_template_strings_without_substitutions() {
+        assert_eq!(
+            parse("`it's \"quoted\"`").unwrap(),
+            json!("it's \"quoted\"")
+        );
+        assert!(parse("`hello ${name}`").is_err());
+    }
+
+    #[test]
+    fn allows_trailing_commas() {
+        assert_eq!(parse("[1, 2,]").unwrap(), json!([1, 2]));
+        assert_eq!(parse("{a: 1, b: [],}").unwrap(), json!({"a": 1, "b": []}));
+        assert!(parse("[1,,2]").is_err());
+    }
+
+    #[test]
+    fn reads_keys_of_every_kind() {
+        assert_eq!(
+            parse(r#"{plain: 1, 'single': 2, "double": 3, 4: 4, $x_1: 5}"#).unwrap(),
+            json!({"plain": 1, "single": 2, "double": 3, "4": 4, "$x_1": 5})
+        );
+    }
+
+    #[test]
+    fn reads_minified_constants() {
+        assert_eq!(
+            parse("[!0, !1, void 0, undefined, null, true, false]").unwrap(),
+            json!([true, false, null, null, null, true, false])
+        );
+        assert!(parse("!2").is_err());
+    }
+
+    #[test]
+    fn reads_numbers() {
+        assert_eq!(
+            parse("[1, -3e2, 2.5, +4, .5]").unwrap(),
+            json!([1, -300.0, 2.5, 4, 0.5])
+        );
+    }
+
+    #[test]
+    fn rejects_identifiers() {
+        let error = parse("{clues: someVariable}").unwrap_err();
+        assert!(error.contains("can't evaluate 'someVariable'"), "{}", error);
+        assert!(parse("[Infinity]").is_err());
+    }
+
+    #[test]
+    fn stops_at_the_end_of_the_literal() {
+        let source = "  {a: [1]}; more();";
+        let (value, length) = parse_prefix(source).unwrap();
+        assert_eq!(value, json!({"a": [1]}));
+        assert_eq!(&source[length..], "; more();");
+    }
+}
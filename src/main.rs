@@ -1,293 +1,631 @@
+mod admin;
+mod check;
+mod cli;
+mod clues;
+mod config;
+mod db;
+mod difficulty;
+mod error;
+mod fetch_puzzles;
+mod freshness;
+mod generation;
 mod generator;
+mod grid;
+mod hints;
+mod import_puzzles;
+mod import_words;
+mod js_object;
+mod migrations;
 mod models;
+mod moderation;
+mod scheduler;
+mod solver;
 
 use actix_files as fs;
-use actix_web::{web, App, HttpResponse, HttpServer, Responder};
-use models::DailyPuzzle;
-use rusqlite::Connection;
-use std::sync::Mutex;
-use std::time::Instant;
-
-use crate::models::{ClueWord, Puzzle};
-
-type AppResult<T> = Result<T, String>;
-
-struct AppState {
-    db: Mutex<Connection>,
-}
-
-fn load_all_clues() -> AppResult<Vec<ClueWord>> {
-    let conn = Connection::open("quinta.db").map_err(|e| format!("DB connection failed: {}", e))?;
-
-    let mut stmt = conn
-        .prepare("SELECT id, clue, word FROM clue_word_pairs")
-        .map_err(|e| format!("select failed: {}", e))?;
-
-    let result = stmt
-        .query_map([], |row| {
-            let id: i64 = row.get(0)?;
-            let clue: String = row.get(1)?;
-            let word: String = row.get(2)?;
-            Ok(ClueWord { id, word, clue })
-        })
-        .and_then(|e| e.collect());
-
-    match result {
-        Ok(data) => Ok(data),
-        Err(e) => Err(format!("Error loading clues: {:?}", &e)),
+use actix_web::{middleware, web, App, HttpResponse, HttpServer};
+use chrono::NaiveDate;
+use clap::Parser;
+use models::{CheckRequest, DailyPuzzle, Hint, ListQuery, PublicPuzzle, TodayQuery};
+
+use crate::cli::{BlocklistAction, Cli, Command};
+use crate::config::Config;
+use crate::db::Repo;
+use crate::error::{ApiError, ApiResult, Error};
+use crate::generation::{
+    add_hints, build_puzzle, build_puzzle_for_date, clue_strategy, create_puzzle,
+    get_or_start_puzzle, load_rules, load_todays_puzzle, Sources,
+};
+use crate::grid::{Direction, Grid};
+use crate::models::{ClueWord, PuzzleStatus};
+
+/// The puzzle as players may see it: `None` for a draft, and an approved
+/// puzzle is marked published now that it's being served.
+fn release(repo: &Repo, mut puzzle: DailyPuzzle) -> error::Result<Option<DailyPuzzle>> {
+    match puzzle.status {
+        PuzzleStatus::Draft => return Ok(None),
+        PuzzleStatus::Approved => {
+            repo.set_status(
+                &puzzle.date,
+                &[PuzzleStatus::Approved],
+                PuzzleStatus::Published,
+            )?;
+            log::info!("Published puzzle for {}", puzzle.date);
+            puzzle.status = PuzzleStatus::Published;
+        }
+        PuzzleStatus::Published => {}
     }
+    Ok(Some(puzzle))
 }
 
-fn load_words() -> AppResult<Vec<String>> {
-    let conn = Connection::open("quinta.db").map_err(|e| format!("db connection failed: {}", e))?;
+/// Regenerates the puzzle for `date` from its recorded seed and clue
+/// strategy (or the way the scheduler would, if none is recorded) and prints how it differs
+/// from the stored one. With `save`, a missing puzzle is saved.
+fn regenerate(repo: &Repo, date: &str, save: bool) -> error::Result<()> {
+    let stored = load_todays_puzzle(repo, date)?;
+
+    let grid = stored
+        .as_ref()
+        .map_or_else(Grid::daily, |puzzle| puzzle.grid.clone());
+
+    let sources = Sources::load(repo)?;
+    let fresh = match stored.as_ref().and_then(|puzzle| puzzle.seed) {
+        Some(seed) => {
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|e| Error::Invalid(format!("invalid date {}: {}", date, e)))?;
+            let strategy = stored
+                .as_ref()
+                .and_then(|puzzle| puzzle.clue_strategy)
+                .unwrap_or_else(|| clue_strategy(day));
+            let (exclusions, selector) = load_rules(repo, day, &sources, strategy)?;
+            build_puzzle(date, &grid, seed, &sources, &exclusions, &selector)?
+        }
+        None => build_puzzle_for_date(repo, date, date, &grid, &sources)?,
+    };
 
-    let mut stmt = conn
-        .prepare("SELECT * FROM word")
-        .map_err(|e| e.to_string())?;
+    let Some(stored) = stored else {
+        println!("No stored puzzle for {}", date);
+        if save {
+            repo.save_puzzle(&fresh)?;
+        } else {
+            println!("Run again with --save to store the regenerated puzzle");
+        }
+        return Ok(());
+    };
 
-    let result = stmt
-        .query_map([], |row| {
-            let word: String = row.get(1)?;
-            Ok(word)
-        })
-        .and_then(|e| e.collect());
+    if stored.seed.is_none() {
+        println!(
+            "Stored puzzle for {} has no seed (imported or edited?), so differences are expected",
+            date
+        );
+    }
 
-    match result {
-        Ok(data) => Ok(data),
-        Err(rusqlite::Error::QueryReturnedNoRows) => panic!("get words returned no word"),
-        Err(e) => Err(e.to_string()),
+    let mut differences = 0;
+    let stored_words = stored.across_words.iter().chain(&stored.down_words);
+    let fresh_words = fresh.across_words.iter().chain(&fresh.down_words);
+    for (entry, (old, new)) in grid.entries().iter().zip(stored_words.zip(fresh_words)) {
+        if old.word != new.word || old.clue != new.clue {
+            differences += 1;
+            let direction = match entry.direction {
+                Direction::Across => "A",
+                Direction::Down => "D",
+            };
+            println!(
+                "  {}{}: {} \"{}\" -> {} \"{}\"",
+                entry.number, direction, old.word, old.clue, new.word, new.clue
+            );
+        }
+    }
+    if stored.plusword != fresh.plusword {
+        differences += 1;
+        println!("  plusword: {} -> {}", stored.plusword, fresh.plusword);
     }
-}
 
-fn get_word_and_clue_by_id(conn: &Connection, id: i64) -> AppResult<(String, String)> {
-    conn.query_row(
-        "SELECT word, clue FROM clue_word_pairs WHERE id = ?1",
-        [id],
-        |row| {
-            let word: String = row.get(0)?;
-            let clue: String = row.get(1)?;
-            Ok((word, clue))
-        },
-    )
-    .map_err(|e| e.to_string())
+    if differences == 0 {
+        println!("✓ Regenerated puzzle for {} is identical", date);
+    } else {
+        println!("{} difference(s) for {}", differences, date);
+    }
+    Ok(())
 }
 
-fn load_todays_puzzle(date: &str) -> AppResult<Option<DailyPuzzle>> {
-    let conn = Connection::open("quinta.db").map_err(|e| e.to_string())?;
+/// Checks every word, clue and plusword in the database against the whole
+/// blocklist, whichever categories are being filtered on, and prints what
+/// matches. Returns how many matches there were.
+fn audit(repo: &Repo) -> error::Result<usize> {
+    let blocklist = repo.load_blocklist(None)?;
+    println!("Auditing against {} blocklist pattern(s)", blocklist.len());
+    let mut found = 0;
+
+    println!("\nWords:");
+    for word in repo.load_words()? {
+        if let Some(blocked) = blocklist.check(&word) {
+            println!("  {} matches {}", word, blocked);
+            found += 1;
+        }
+    }
 
-    let mut stmt = conn
-        .prepare("SELECT across_word_ids, down_word_ids, plusword FROM puzzles where date = ?1")
-        .map_err(|e| e.to_string())?;
+    println!("\nClues:");
+    for clue_word in repo.load_all_clues(true)? {
+        let Some(blocked) = blocklist
+            .check(&clue_word.word)
+            .or_else(|| blocklist.check(&clue_word.clue))
+        else {
+            continue;
+        };
+        found += 1;
+        let banned = if repo.is_banned(clue_word.id)? {
+            " [banned]"
+        } else {
+            ""
+        };
+        println!(
+            "  #{} {} \"{}\"{} matches {}",
+            clue_word.id, clue_word.word, clue_word.clue, banned, blocked
+        );
+        for (date, status) in repo.puzzles_using(clue_word.id)? {
+            println!("      used {} ({})", date, status.as_str());
+        }
+    }
 
-    let result = stmt.query_row([date], |row| {
-        let across_ids_json: String = row.get(0)?;
-        let down_ids_json: String = row.get(1)?;
-        let plusword: String = row.get(2)?;
+    println!("\nPluswords:");
+    for past in repo.load_history()? {
+        if let Some(blocked) = blocklist.check(&past.plusword) {
+            println!("  {} {} matches {}", past.date, past.plusword, blocked);
+            found += 1;
+        }
+    }
 
-        let across_ids: Vec<i64> = serde_json::from_str(&across_ids_json).unwrap();
-        let down_ids: Vec<i64> = serde_json::from_str(&down_ids_json).unwrap();
+    Ok(found)
+}
 
-        // Fetch actual words from IDs
-        let mut across_words = Vec::new();
-        for id in across_ids {
-            if let Ok((word, clue)) = get_word_and_clue_by_id(&conn, id) {
-                across_words.push(ClueWord { id, word, clue });
-            }
+/// `quinta generate --date`: generates and saves the puzzle for `date`,
+/// then prints it. With `replace`, a stored puzzle that hasn't been
+/// published yet is swapped for a new one, as the admin API's regenerate
+/// does.
+fn generate(repo: &Repo, date: &str, replace: bool) -> error::Result<()> {
+    match load_todays_puzzle(repo, date)? {
+        None => {
+            create_puzzle(repo, date)?;
         }
-
-        let mut down_words = Vec::new();
-        for id in down_ids {
-            if let Ok((word, clue)) = get_word_and_clue_by_id(&conn, id) {
-                down_words.push(ClueWord { id, word, clue });
-            }
+        Some(_) if !replace => {
+            println!(
+                "A puzzle for {} is already stored; use --replace for a new one",
+                date
+            );
+            return Ok(());
         }
-
-        Ok(add_hints(Puzzle {
-            date: date.to_owned(),
-            across_words,
-            down_words,
-            plusword,
-        }))
-    });
-
-    match result {
-        Ok(data) => {
-            println!("Loaded existing puzzle for {}", date);
-            Ok(Some(data))
+        Some(stored) if stored.status == PuzzleStatus::Published => {
+            return Err(Error::Invalid(format!(
+                "the puzzle for {} is published and can't be replaced",
+                date
+            )));
+        }
+        Some(stored) => {
+            let label = format!("{}~{:08x}", date, rand::random::<u32>());
+            let sources = Sources::load(repo)?;
+            let puzzle = build_puzzle_for_date(repo, date, &label, &stored.grid, &sources)?;
+            if !repo.replace_puzzle(&puzzle)? {
+                return Err(Error::Generation(format!(
+                    "the puzzle for {} was published while generating",
+                    date
+                )));
+            }
         }
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e.to_string()),
     }
+    println!();
+    show(repo, date, false)
 }
 
-/*
-fn get_clue_for_word(word: &str) -> AppResult<String> {
-    let conn = Connection::open("quinta.db").map_err(|e| e.to_string())?;
-
-    let mut stmt = conn
-        .prepare("SELECT clue FROM clue_word_pairs WHERE word = ?1 LIMIT 1")
-        .map_err(|e| e.to_string())?;
-
-    stmt.query_row([word], |row| row.get(0))
-        .map_err(|e| e.to_string())?
-}
-*/
+/// `quinta show --date`: prints the puzzle stored for `date`, whatever its
+/// status, with its answers and each row's plusword hints (G green, Y
+/// yellow).
+fn show(repo: &Repo, date: &str, json: bool) -> error::Result<()> {
+    let Some(puzzle) = load_todays_puzzle(repo, date)? else {
+        return Err(Error::NotFound(format!("no puzzle stored for {}", date)));
+    };
+    if json {
+        let json = serde_json::to_string_pretty(&puzzle).map_err(|e| Error::Data(e.to_string()))?;
+        println!("{}", json);
+        return Ok(());
+    }
 
-fn get_word_id(conn: &Connection, word: &str) -> AppResult<Option<i64>> {
-    let result = conn.query_row(
-        "SELECT id FROM clue_word_pairs WHERE word = ?1 LIMIT 1",
-        [word],
-        |row| row.get(0),
+    println!(
+        "Puzzle #{} for {} ({})",
+        puzzle.number,
+        puzzle.date,
+        puzzle.status.as_str()
     );
+    if let Some(difficulty) = puzzle.difficulty {
+        println!("Difficulty: {:.1}", difficulty);
+    }
+    if let Some(strategy) = puzzle.clue_strategy {
+        println!("Clue strategy: {}", strategy);
+    }
+    if let Some(seed) = puzzle.seed {
+        println!("Seed: {:016x}", seed);
+    }
 
-    match result {
-        Ok(id) => Ok(Some(id)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e.to_string()),
+    let entries = puzzle.grid.entries();
+    let answers: Vec<(&grid::Entry, &ClueWord)> = entries
+        .iter()
+        .zip(puzzle.across_words.iter().chain(&puzzle.down_words))
+        .collect();
+    let mut cells = vec![vec!['#'; puzzle.grid.width]; puzzle.grid.height];
+    for (entry, clue_word) in &answers {
+        for ((row, col), letter) in entry.cells().into_iter().zip(clue_word.word.chars()) {
+            cells[row][col] = letter.to_ascii_uppercase();
+        }
+    }
+    println!();
+    for (row, hints) in cells.iter().zip(&puzzle.hints) {
+        let letters: Vec<String> = row.iter().map(char::to_string).collect();
+        let hints: Vec<&str> = hints
+            .iter()
+            .map(|hint| match hint {
+                Some(Hint::Green) => "G",
+                Some(Hint::Yellow) => "Y",
+                None => ".",
+            })
+            .collect();
+        println!("  {}    {}", letters.join(" "), hints.join(" "));
+    }
+    println!("\nPlusword: {}", puzzle.plusword);
+
+    for direction in [Direction::Across, Direction::Down] {
+        println!(
+            "\n{}:",
+            match direction {
+                Direction::Across => "Across",
+                Direction::Down => "Down",
+            }
+        );
+        for (entry, clue_word) in answers.iter().filter(|(e, _)| e.direction == direction) {
+            println!(
+                "  {:>2}. {} - {} [#{}]",
+                entry.number, clue_word.word, clue_word.clue, clue_word.id
+            );
+        }
     }
+    Ok(())
 }
 
-fn save_puzzle_to_db(puzzle: &Puzzle) -> AppResult<()> {
-    let conn = Connection::open("quinta.db").map_err(|e| format!("DB connection failed: {}", e))?;
-
-    // Get word IDs for across and down words
-    let mut across_word_ids = Vec::new();
-    for clue_word in &puzzle.across_words {
-        if let Some(id) = get_word_id(&conn, &clue_word.word)? {
-            across_word_ids.push(id);
-        } else {
-            eprintln!("Warning: No ID found for word '{}'", &clue_word.word);
+/// `quinta validate-db`: checks the schema is current, that SQLite finds no
+/// corruption or dangling references, and that every stored puzzle loads
+/// with answers that fit its grid. Returns the problems found.
+fn validate_db(repo: &Repo) -> error::Result<Vec<String>> {
+    // Nothing else can be trusted to query an out-of-date schema.
+    if let Err(e) = repo.check_schema() {
+        return Ok(vec![e.to_string()]);
+    }
+    let mut problems = repo.integrity_problems()?;
+
+    let dates = repo.puzzle_dates()?;
+    for date in &dates {
+        let puzzle = match repo.load_puzzle(date) {
+            Ok(Some((puzzle, _))) => puzzle,
+            Ok(None) => continue,
+            Err(e) => {
+                problems.push(format!("{}: {}", date, e));
+                continue;
+            }
+        };
+        let answers = puzzle.across_words.len() + puzzle.down_words.len();
+        match answers.checked_sub(1) {
+            None => problems.push(format!("{}: the puzzle has no answers", date)),
+            Some(last) => {
+                if let Err(e) =
+                    admin::check_fit(&puzzle.grid, &puzzle.across_words, &puzzle.down_words, last)
+                {
+                    problems.push(format!("{}: {}", date, e));
+                }
+            }
+        }
+        if puzzle.plusword.chars().count() != puzzle.grid.width {
+            problems.push(format!(
+                "{}: plusword {} doesn't fit a grid {} wide",
+                date, puzzle.plusword, puzzle.grid.width
+            ));
         }
     }
+    println!("Checked {} puzzle(s)", dates.len());
+    Ok(problems)
+}
 
-    let mut down_word_ids = Vec::new();
-    for clue_word in &puzzle.down_words {
-        if let Some(id) = get_word_id(&conn, &clue_word.word)? {
-            down_word_ids.push(id);
-        } else {
-            eprintln!("Warning: No ID found for word '{}'", &clue_word.word);
+/// `quinta blocklist ...`: lists, adds or removes blocklist patterns.
+fn blocklist_command(repo: &Repo, action: Option<BlocklistAction>) -> error::Result<()> {
+    match action.unwrap_or(BlocklistAction::List) {
+        BlocklistAction::List => {
+            for blocked in repo.list_blocklist()? {
+                println!("{}", blocked);
+            }
+        }
+        BlocklistAction::Add {
+            pattern,
+            substring,
+            category,
+        } => {
+            let pattern = pattern.trim().to_lowercase();
+            if pattern.is_empty() {
+                return Err(Error::Invalid("the pattern can't be empty".to_owned()));
+            }
+            let blocked = moderation::BlockedPattern {
+                pattern,
+                kind: match substring {
+                    true => moderation::MatchKind::Substring,
+                    false => moderation::MatchKind::Word,
+                },
+                category: category.trim().to_lowercase(),
+            };
+            repo.add_blocklist_pattern(&blocked)?;
+            println!("Blocked {}", blocked);
+        }
+        BlocklistAction::Remove { pattern } => {
+            let pattern = pattern.trim().to_lowercase();
+            match repo.remove_blocklist_pattern(&pattern)? {
+                0 => println!("\"{}\" isn't on the blocklist", pattern),
+                _ => println!("Unblocked \"{}\"", pattern),
+            }
         }
     }
-
-    let across_json = serde_json::to_string(&across_word_ids).unwrap();
-    let down_json = serde_json::to_string(&down_word_ids).unwrap();
-
-    conn.execute(
-        "INSERT INTO puzzles (date, across_word_ids, down_word_ids, plusword) VALUES (?1, ?2, ?3, ?4)",
-        (&puzzle.date, &across_json, &down_json, &puzzle.plusword),
-    ).map_err(|e| format!("insert failed: {}", e))?;
-
-    println!("✓ Saved puzzle to database for {}", puzzle.date);
-
     Ok(())
 }
 
-fn create_todays_puzzle() -> AppResult<DailyPuzzle> {
-    let overall_start = Instant::now();
-    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-
-    println!("Generating new puzzle for {}", today);
+const MAX_PER_PAGE: u32 = 100;
 
-    let clue_words = load_all_clues().expect("Failed to load clues");
-    println!("Loaded {} clue-word pairs", clue_words.len());
+fn today() -> NaiveDate {
+    config::get().puzzle.today()
+}
 
-    // Generate a valid crossword with brute force
-    let (across_words, down_words) = match generator::generate_crossword(&clue_words) {
-        Some(result) => result,
-        None => {
-            return Err(format!("Failed to generate crossword, using fallback").to_string());
-        }
-    };
+async fn get_today_puzzle(
+    repo: web::Data<Repo>,
+    query: web::Query<TodayQuery>,
+) -> ApiResult<HttpResponse> {
+    let date = config::get()
+        .puzzle
+        .today_for(query.date)
+        .map_err(ApiError::BadRequest)?;
+    get_puzzle(repo, date).await
+}
 
-    let words = load_words().expect("Failed to load words");
+/// Parses a `YYYY-MM-DD` date from a request, refusing dates after
+/// `PuzzleConfig::latest` so upcoming puzzles can't be read (or checked)
+/// ahead of time.
+fn playable_date(date: &str) -> ApiResult<NaiveDate> {
+    let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest(format!("invalid date: {}", date)))?;
 
-    let plusword = generator::choose_plusword(
-        &words,
-        &across_words
-            .iter()
-            .map(|cw| cw.word.clone())
-            .collect::<Vec<String>>(),
-    )
-    .to_uppercase();
-
-    println!("Selected plusword: {}", plusword);
-
-    let puzzle = Puzzle {
-        date: today.clone(),
-        across_words: across_words.clone(),
-        down_words: down_words.clone(),
-        plusword: plusword.clone(),
-    };
-
-    // Save to database
-    if let Err(e) = save_puzzle_to_db(&puzzle) {
-        eprintln!("Failed to save puzzle to database: {}", e);
+    if parsed > config::get().puzzle.latest() {
+        return Err(ApiError::NotAvailable(date.to_owned()));
     }
+    Ok(parsed)
+}
 
-    let total_elapsed = overall_start.elapsed();
-    println!(
-        "✓ Total puzzle generation time: {:.2}s",
-        total_elapsed.as_secs_f64()
-    );
+async fn get_puzzle_by_date(
+    repo: web::Data<Repo>,
+    path: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let date = playable_date(&path)?;
+    get_puzzle(repo, date).await
+}
 
-    Ok(add_hints(puzzle))
+/// The puzzle for a playable `date`. Only today's and later are generated
+/// on demand: filling a gap in the past would renumber the series.
+async fn get_puzzle(repo: web::Data<Repo>, date: NaiveDate) -> ApiResult<HttpResponse> {
+    let current = date >= today();
+    let date = date.format("%Y-%m-%d").to_string();
+
+    let puzzle = web::block(move || {
+        let puzzle = match current {
+            // Normally the scheduler has already generated the puzzle; this
+            // only generates on demand if it hasn't caught up yet.
+            true => get_or_start_puzzle(&repo, &date)?,
+            false => load_todays_puzzle(&repo, &date)?
+                .ok_or_else(|| ApiError::NotFound(format!("no puzzle for {}", date)))?,
+        };
+        release(&repo, puzzle)?.ok_or(match current {
+            true => ApiError::AwaitingReview(date),
+            false => ApiError::NotFound(format!("no puzzle for {}", date)),
+        })
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok().json(PublicPuzzle::from(&puzzle)))
 }
 
-fn add_hints(puzzle: Puzzle) -> DailyPuzzle {
-    let hints = generator::hints(
-        &puzzle.plusword,
-        &puzzle
-            .across_words
-            .iter()
-            .map(|cw| cw.word.clone())
-            .collect(),
-    );
+async fn list_puzzles_handler(
+    repo: web::Data<Repo>,
+    query: web::Query<ListQuery>,
+) -> ApiResult<HttpResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(30).clamp(1, MAX_PER_PAGE);
+    let latest = config::get().puzzle.latest().format("%Y-%m-%d").to_string();
 
-    DailyPuzzle {
-        date: puzzle.date,
-        across_words: puzzle.across_words,
-        down_words: puzzle.down_words,
-        hints: hints.into_iter().map(|row| row.to_vec()).collect(),
-        plusword: puzzle.plusword,
-    }
+    let list = web::block(move || repo.list_puzzles(&latest, page, per_page)).await??;
+    Ok(HttpResponse::Ok().json(list))
 }
 
-async fn get_today_puzzle(_data: web::Data<AppState>) -> impl Responder {
-    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+async fn check_puzzle(
+    repo: web::Data<Repo>,
+    request: web::Json<CheckRequest>,
+) -> ApiResult<HttpResponse> {
+    playable_date(&request.date)?;
+
+    let puzzle = web::block({
+        let date = request.date.clone();
+        move || match load_todays_puzzle(&repo, &date)? {
+            Some(puzzle) => release(&repo, puzzle),
+            None => Ok(None),
+        }
+    })
+    .await??
+    .ok_or_else(|| ApiError::NotFound(format!("no puzzle for {}", request.date)))?;
 
-    let puzzle = match load_todays_puzzle(&today) {
-        Ok(Some(puzzle)) => Ok(puzzle),
-        Ok(None) => create_todays_puzzle(),
-        Err(e) => Err(e),
-    };
+    Ok(HttpResponse::Ok().json(check::check_answers(&puzzle, &request)))
+}
 
-    match puzzle {
-        Ok(puzzle) => HttpResponse::Ok().json(puzzle),
-        Err(e) => HttpResponse::InternalServerError().body(format!("error: {}", e)),
+/// Runs every subcommand but `serve` and the standalone tools.
+fn run_command(config: &Config, command: Command) -> error::Result<()> {
+    let repo = Repo::open(&config.database.path)?;
+
+    match command {
+        Command::Migrate => {
+            let applied = repo.migrate(config.puzzle.today())?;
+            if applied.is_empty() {
+                println!("Database is already up to date");
+            } else {
+                println!("Applied {} migration(s)", applied.len());
+            }
+            return Ok(());
+        }
+        Command::ValidateDb => {
+            let problems = validate_db(&repo)?;
+            if problems.is_empty() {
+                println!("✓ No problems found");
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("  ✗ {}", problem);
+            }
+            return Err(Error::Data(format!("{} problem(s) found", problems.len())));
+        }
+        _ => repo.check_schema()?,
     }
-}
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    println!("Starting Quinta server at http://localhost:8080");
+    match command {
+        Command::Generate { date, replace } => generate(&repo, &date.to_string(), replace)?,
+        Command::Show { date, json } => show(&repo, &date.to_string(), json)?,
+        Command::Pregenerate { days } => {
+            let days_ahead = days.unwrap_or(config.generation.days_ahead);
+            let generated = scheduler::fill_ahead(&repo, today(), days_ahead)?;
+            println!("Generated {} new puzzle(s)", generated.len());
+        }
+        Command::Sample { shape, seed } => {
+            let grid = Grid::preset(&shape)
+                .ok_or_else(|| Error::Invalid(format!("unknown grid shape: {}", shape)))?;
+            let day = today();
+            let date = day.format("%Y-%m-%d").to_string();
+            let seed = seed.unwrap_or_else(rand::random);
+            let sources = Sources::load(&repo)?;
+            let (exclusions, selector) = load_rules(&repo, day, &sources, clue_strategy(day))?;
+            let puzzle = build_puzzle(&date, &grid, seed, &sources, &exclusions, &selector)?;
+            let json = serde_json::to_string_pretty(&add_hints(puzzle, 0)?)
+                .map_err(|e| Error::Data(e.to_string()))?;
+            println!("{}", json);
+        }
+        Command::RateClue { clue_id, rating } => match repo.set_clue_rating(clue_id, rating)? {
+            Some(clue_word) => println!(
+                "Rated {} \"{}\" {}/5",
+                clue_word.word, clue_word.clue, rating
+            ),
+            None => return Err(Error::NotFound(format!("no clue with id {}", clue_id))),
+        },
+        Command::Blocklist { action } => blocklist_command(&repo, action)?,
+        Command::Audit => {
+            let found = audit(&repo)?;
+            if found > 0 {
+                return Err(Error::Data(format!(
+                    "\n{} blocklist match(es) found",
+                    found
+                )));
+            }
+            println!("\nNothing matches the blocklist");
+        }
+        Command::Regenerate { date, save } => regenerate(&repo, &date.to_string(), save)?,
+        Command::Serve
+        | Command::Config
+        | Command::Migrate
+        | Command::ValidateDb
+        | Command::FetchPuzzles(_)
+        | Command::ImportPuzzles(_)
+        | Command::ImportWords(_) => unreachable!("handled elsewhere"),
+    }
+    Ok(())
+}
 
-    //let clue_words = load_all_clues().expect("Failed to load clues");
-    //generate_crossword(&clue_words);
+async fn serve(config: &'static Config) -> error::Result<()> {
+    let server = &config.server;
+    if !server.static_dir.is_dir() {
+        return Err(Error::Config(format!(
+            "static directory {} doesn't exist (set server.static_dir)",
+            server.static_dir.display()
+        )));
+    }
+    let repo = Repo::open(&config.database.path)?;
+    repo.check_schema()?;
 
-    //return Ok(());
+    log::info!(
+        "Starting Quinta server at http://{}:{}",
+        server.bind,
+        server.port
+    );
 
-    let _conn = Connection::open("quinta.db")
-        .expect("Failed to open database. Make sure quinta.db exists.");
+    actix_web::rt::spawn(scheduler::run(
+        repo.clone(),
+        config.generation.days_ahead,
+        scheduler::CHECK_INTERVAL,
+    ));
+
+    let admin_token = config
+        .admin
+        .token
+        .clone()
+        .map(|token| web::Data::new(admin::AdminToken(token)));
+    match admin_token {
+        Some(_) => log::info!("Admin API enabled; new puzzles need approval before they're served"),
+        None => log::info!("Admin API disabled (set admin.token to enable it)"),
+    }
 
+    let repo = web::Data::new(repo);
     HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(AppState {
-                db: Mutex::new(Connection::open("quinta.db").unwrap()),
-            }))
-            .route("/api/puzzle/today", web::get().to(get_today_puzzle))
-            .service(fs::Files::new("/", "./static").index_file("index.html"))
+        let mut app = App::new()
+            .wrap(middleware::Logger::default())
+            .app_data(repo.clone())
+            .app_data(web::QueryConfig::default().error_handler(ApiError::from_extractor))
+            .app_data(web::PathConfig::default().error_handler(ApiError::from_extractor))
+            .app_data(web::JsonConfig::default().error_handler(ApiError::from_extractor));
+        if let Some(token) = &admin_token {
+            app = app.service(admin::scope().app_data(token.clone()));
+        }
+        app.route("/api/puzzle/today", web::get().to(get_today_puzzle))
+            .route("/api/puzzle/check", web::post().to(check_puzzle))
+            .route("/api/puzzle/{date}", web::get().to(get_puzzle_by_date))
+            .route("/api/puzzles", web::get().to(list_puzzles_handler))
+            .service(fs::Files::new("/", &server.static_dir).index_file("index.html"))
     })
-    .bind(("127.0.0.1", 8080))?
+    .bind((server.bind.as_str(), server.port))
+    .map_err(|e| Error::Io(format!("binding {}:{}", server.bind, server.port), e))?
     .run()
     .await
+    .map_err(|e| Error::Io("serving".to_owned(), e))
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+    let config = match Config::load(&cli.settings) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    env_logger::Builder::new()
+        .filter_level(config.log.level)
+        .init();
+    config::init(config);
+    let config = config::get();
+
+    let result = match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(config).await,
+        Command::Config => config.to_redacted_toml().map(|toml| print!("{}", toml)),
+        Command::FetchPuzzles(args) => fetch_puzzles::run(args),
+        Command::ImportPuzzles(args) => import_puzzles::run(&config.database.path, args),
+        Command::ImportWords(args) => import_words::run(&config.database.path, args),
+        command => run_command(config, command),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    Ok(())
 }
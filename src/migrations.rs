@@ -0,0 +1,314 @@
+use chrono::NaiveDate;
+use rusqlite::{Connection, Result, Transaction};
+
+/// One step in the schema's history.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    up: fn(&Transaction) -> Result<()>,
+    /// Brings existing rows in line with the new schema where that depends
+    /// on today's puzzle date, which the caller supplies.
+    backfill: Option<fn(&Transaction, NaiveDate) -> Result<()>>,
+}
+
+/// Every schema change, oldest first. Shared by the server and the
+/// importers so they all agree on what the database looks like.
+///
+/// Each migration runs once, in its own transaction, and is recorded in the
+/// `schema_version` table. To change the schema, append a new migration;
+/// never edit one that has already shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create clue_word_pairs, word and puzzles",
+        up: create_base_tables,
+        backfill: None,
+    },
+    Migration {
+        version: 2,
+        description: "record grid shape, seed and difficulty on puzzles",
+        up: add_puzzle_generation_columns,
+        backfill: None,
+    },
+    Migration {
+        version: 3,
+        description: "move puzzle answers from JSON id arrays to puzzle_entries",
+        up: create_puzzle_entries,
+        backfill: None,
+    },
+    Migration {
+        version: 4,
+        description: "add clue_ratings and record each puzzle's clue strategy",
+        up: add_clue_selection,
+        backfill: None,
+    },
+    Migration {
+        version: 5,
+        description: "add editorial status to puzzles and clue_bans",
+        up: add_editorial_review,
+        backfill: Some(approve_upcoming),
+    },
+    Migration {
+        version: 6,
+        description: "add blocklist",
+        up: create_blocklist,
+        backfill: None,
+    },
+    Migration {
+        version: 7,
+        description: "add quarantined_puzzles",
+        up: create_quarantine,
+        backfill: None,
+    },
+    Migration {
+        version: 8,
+        description: "add frequency, commonness and part of speech to word",
+        up: add_word_metadata,
+        backfill: None,
+    },
+];
+
+/// The version `conn` has been migrated to; 0 if it never has.
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    let tracked: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !tracked {
+        return Ok(0);
+    }
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Migrations `conn` hasn't had yet, oldest first.
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let current = current_version(conn)?;
+    Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+}
+
+/// Applies every pending migration, backfilling against `today`'s puzzle
+/// date. Returns the versions applied.
+pub fn migrate(conn: &mut Connection, today: NaiveDate) -> Result<Vec<u32>> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+
+    let mut applied = Vec::new();
+    for migration in pending(conn)? {
+        println!(
+            "Applying migration {}: {}",
+            migration.version, migration.description
+        );
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        if let Some(backfill) = migration.backfill {
+            backfill(&tx, today)?;
+        }
+        tx.execute(
+            "INSERT INTO schema_version (version, description) VALUES (?1, ?2)",
+            (migration.version, migration.description),
+        )?;
+        tx.commit()?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+fn column_exists(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )
+}
+
+/// The tables as the importers used to create them. `IF NOT EXISTS` so
+/// databases from before migrations existed are adopted as they are.
+fn create_base_tables(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS clue_word_pairs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            word TEXT NOT NULL,
+            clue TEXT NOT NULL,
+            slug TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(word, clue)
+        );
+
+        CREATE TABLE IF NOT EXISTS word (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            word TEXT NOT NULL,
+            UNIQUE(word)
+        );
+
+        CREATE TABLE IF NOT EXISTS puzzles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL UNIQUE,
+            plusword TEXT NOT NULL,
+            across_word_ids TEXT NOT NULL,
+            down_word_ids TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+/// Older servers added these columns on startup, so some databases already
+/// have them.
+fn add_puzzle_generation_columns(tx: &Transaction) -> Result<()> {
+    for (column, definition) in [
+        ("grid", "TEXT"),
+        ("seed", "INTEGER"),
+        ("difficulty", "REAL"),
+    ] {
+        if !column_exists(tx, "puzzles", column)? {
+            tx.execute(
+                &format!("ALTER TABLE puzzles ADD COLUMN {} {}", column, definition),
+                [],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// One row per answer in a puzzle, replacing the `across_word_ids` and
+/// `down_word_ids` JSON arrays. `position` is the answer's index among the
+/// puzzle's across or down entries, in `Grid::entries` order.
+///
+/// Ids that no longer match a clue are dropped rather than copied, so the
+/// puzzles they belonged to show up as incomplete instead of breaking the
+/// foreign key.
+fn create_puzzle_entries(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE puzzle_entries (
+            puzzle_id INTEGER NOT NULL REFERENCES puzzles(id) ON DELETE CASCADE,
+            direction TEXT NOT NULL CHECK (direction IN ('across', 'down')),
+            position INTEGER NOT NULL CHECK (position >= 0),
+            clue_word_id INTEGER NOT NULL REFERENCES clue_word_pairs(id),
+            PRIMARY KEY (puzzle_id, direction, position)
+        );
+
+        CREATE INDEX puzzle_entries_clue_word_id ON puzzle_entries(clue_word_id);",
+    )?;
+
+    for (direction, column) in [("across", "across_word_ids"), ("down", "down_word_ids")] {
+        tx.execute(
+            &format!(
+                "INSERT INTO puzzle_entries (puzzle_id, direction, position, clue_word_id)
+                 SELECT puzzles.id, ?1, ids.key, ids.value
+                 FROM puzzles, json_each(puzzles.{column}) AS ids
+                 WHERE ids.value IN (SELECT id FROM clue_word_pairs)"
+            ),
+            [direction],
+        )?;
+
+        let dangling: i64 = tx.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM puzzles, json_each(puzzles.{column}) AS ids
+                 WHERE ids.value NOT IN (SELECT id FROM clue_word_pairs)"
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        if dangling > 0 {
+            log::warn!(
+                "Dropped {} {} answer(s) whose clue no longer exists",
+                dangling,
+                direction
+            );
+        }
+    }
+
+    tx.execute_batch(
+        "ALTER TABLE puzzles DROP COLUMN across_word_ids;
+         ALTER TABLE puzzles DROP COLUMN down_word_ids;",
+    )
+}
+
+/// Editorial ratings for clue selection, and the strategy each generated
+/// puzzle's clues were picked with (as JSON, `NULL` for imported puzzles).
+fn add_clue_selection(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE clue_ratings (
+            clue_word_id INTEGER PRIMARY KEY REFERENCES clue_word_pairs(id) ON DELETE CASCADE,
+            rating INTEGER NOT NULL CHECK (rating BETWEEN 1 AND 5),
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );
+
+        ALTER TABLE puzzles ADD COLUMN clue_strategy TEXT;",
+    )
+}
+
+/// Puzzles stored before review existed were going to be served as they
+/// are, so they start out published; `approve_upcoming` then moves the
+/// ones not yet served back to approved.
+fn add_editorial_review(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE puzzles ADD COLUMN status TEXT NOT NULL DEFAULT 'published'
+            CHECK (status IN ('draft', 'approved', 'published'));
+
+        CREATE TABLE clue_bans (
+            clue_word_id INTEGER PRIMARY KEY REFERENCES clue_word_pairs(id) ON DELETE CASCADE,
+            reason TEXT,
+            banned_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+/// Puzzles after `today`'s puzzle date haven't been served yet, so they
+/// wait as approved rather than published.
+fn approve_upcoming(tx: &Transaction, today: NaiveDate) -> Result<()> {
+    tx.execute(
+        "UPDATE puzzles SET status = 'approved' WHERE date > ?1",
+        [today.format("%Y-%m-%d").to_string()],
+    )?;
+    Ok(())
+}
+
+/// Patterns no puzzle may contain; see `moderation`. `category` lets a
+/// deployment choose which kinds of pattern it filters on.
+fn create_blocklist(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE blocklist (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pattern TEXT NOT NULL CHECK (pattern <> '' AND pattern = lower(pattern)),
+            kind TEXT NOT NULL CHECK (kind IN ('word', 'substring')),
+            category TEXT NOT NULL DEFAULT 'offensive',
+            added_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(pattern, kind)
+        );",
+    )
+}
+
+/// Fetched puzzles the importer couldn't make sense of, kept with the
+/// reason and the clues as fetched (JSON) so they can be looked at later.
+fn create_quarantine(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE quarantined_puzzles (
+            date TEXT PRIMARY KEY,
+            reason TEXT NOT NULL,
+            clues TEXT NOT NULL,
+            quarantined_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+/// What word lists can say about a word. `frequency` is as the list gave
+/// it; `commonness` scales it from 0 (rarest in that list) to 1 (most
+/// common), so lists with different kinds of counts can be compared.
+fn add_word_metadata(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE word ADD COLUMN frequency REAL;
+        ALTER TABLE word ADD COLUMN commonness REAL CHECK (commonness BETWEEN 0 AND 1);
+        ALTER TABLE word ADD COLUMN part_of_speech TEXT;",
+    )
+}
@@ -1,14 +1,56 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::clues::ClueStrategy;
+use crate::grid::{Direction, Grid};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Puzzle {
     pub date: String,
+    pub grid: Grid,
+    /// Seed the puzzle was generated from; `None` for imported puzzles.
+    pub seed: Option<u64>,
     pub across_words: Vec<ClueWord>,
     pub down_words: Vec<ClueWord>,
     pub plusword: String,
+    /// Rating from 1 (easy) to 5 (hard); `None` for imported puzzles.
+    pub difficulty: Option<f64>,
+    /// How the clues were picked; `None` for imported puzzles.
+    pub clue_strategy: Option<ClueStrategy>,
+    pub status: PuzzleStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Where a puzzle is in editorial review. Drafts are never shown to
+/// players; an approved puzzle becomes published the first time it is
+/// served, and published puzzles can no longer be edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PuzzleStatus {
+    Draft,
+    Approved,
+    Published,
+}
+
+impl PuzzleStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PuzzleStatus::Draft => "draft",
+            PuzzleStatus::Approved => "approved",
+            PuzzleStatus::Published => "published",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "draft" => Some(PuzzleStatus::Draft),
+            "approved" => Some(PuzzleStatus::Approved),
+            "published" => Some(PuzzleStatus::Published),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClueWord {
     pub id: i64,
     pub word: String,
@@ -18,14 +60,133 @@ pub struct ClueWord {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DailyPuzzle {
     pub date: String,
+    pub number: i64,
+    pub grid: Grid,
+    pub seed: Option<u64>,
     pub across_words: Vec<ClueWord>,
     pub down_words: Vec<ClueWord>,
     pub hints: Vec<Vec<Option<Hint>>>,
     pub plusword: String,
+    pub difficulty: Option<f64>,
+    pub clue_strategy: Option<ClueStrategy>,
+    pub status: PuzzleStatus,
 }
 
-#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Hint {
     Yellow,
     Green,
 }
+
+/// A clue as shown to the player, without its answer.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PuzzleClue {
+    pub number: usize,
+    pub row: usize,
+    pub col: usize,
+    pub clue: String,
+    pub length: usize,
+}
+
+/// The puzzle as served to the browser. Everything needed to render and
+/// play the grid, but none of the answers; those stay on the server and are
+/// only compared against through `POST /api/puzzle/check`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicPuzzle {
+    pub date: String,
+    pub number: i64,
+    pub width: usize,
+    pub height: usize,
+    /// One string per row, `.` for an open cell and `#` for a block.
+    pub grid: Grid,
+    pub across_clues: Vec<PuzzleClue>,
+    pub down_clues: Vec<PuzzleClue>,
+    pub hints: Vec<Vec<Option<Hint>>>,
+    pub plusword_length: usize,
+    pub difficulty: Option<f64>,
+}
+
+impl From<&DailyPuzzle> for PublicPuzzle {
+    fn from(puzzle: &DailyPuzzle) -> Self {
+        let mut across_clues = Vec::new();
+        let mut down_clues = Vec::new();
+        let words = puzzle.across_words.iter().chain(&puzzle.down_words);
+
+        for (entry, clue_word) in puzzle.grid.entries().into_iter().zip(words) {
+            let clue = PuzzleClue {
+                number: entry.number,
+                row: entry.row,
+                col: entry.col,
+                clue: clue_word.clue.clone(),
+                length: entry.length,
+            };
+            match entry.direction {
+                Direction::Across => across_clues.push(clue),
+                Direction::Down => down_clues.push(clue),
+            }
+        }
+
+        PublicPuzzle {
+            date: puzzle.date.clone(),
+            number: puzzle.number,
+            width: puzzle.grid.width,
+            height: puzzle.grid.height,
+            grid: puzzle.grid.clone(),
+            across_clues,
+            down_clues,
+            hints: puzzle.hints.clone(),
+            plusword_length: puzzle.plusword.chars().count(),
+            difficulty: puzzle.difficulty,
+        }
+    }
+}
+
+/// A (possibly partial) solution submitted by the player. Cells are single
+/// letters; empty strings are cells the player hasn't filled in yet.
+#[derive(Debug, Deserialize)]
+pub struct CheckRequest {
+    pub date: String,
+    pub grid: Vec<Vec<String>>,
+    pub plusword: Vec<String>,
+}
+
+/// Per-cell result of a check: `Some(true)` for a correct letter,
+/// `Some(false)` for a wrong one and `None` for an empty cell.
+#[derive(Debug, Serialize)]
+pub struct CheckResponse {
+    pub cells: Vec<Vec<Option<bool>>>,
+    pub plusword: Vec<Option<bool>>,
+    /// Wordle-style colours for the plusword guess, once every letter of it
+    /// has been filled in.
+    pub plusword_hints: Option<Vec<Option<Hint>>>,
+    pub solved: bool,
+}
+
+/// Query parameters for `GET /api/puzzles`.
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Query parameters for `GET /api/puzzle/today`.
+#[derive(Debug, Deserialize)]
+pub struct TodayQuery {
+    /// The player's local date, honoured in client-local mode.
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PuzzleSummary {
+    pub date: String,
+    pub number: i64,
+}
+
+/// One page of the puzzle archive, newest first.
+#[derive(Debug, Serialize)]
+pub struct PuzzleList {
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+    pub puzzles: Vec<PuzzleSummary>,
+}
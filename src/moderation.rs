@@ -0,0 +1,116 @@
+//! The blocklist: words and phrases that must never appear in a puzzle,
+//! as an answer, a clue or a plusword. Shared by the server and the
+//! importers, like `migrations`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use rusqlite::{Connection, Result};
+
+/// How a blocklist pattern is matched against text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchKind {
+    /// A whole word of the text, ignoring case.
+    Word,
+    /// Anywhere in the text, ignoring case, even inside a longer word.
+    Substring,
+}
+
+impl MatchKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatchKind::Word => "word",
+            MatchKind::Substring => "substring",
+        }
+    }
+
+    /// Reads a kind as stored; anything unrecognised is a whole word.
+    pub fn parse(kind: &str) -> Self {
+        match kind {
+            "substring" => MatchKind::Substring,
+            _ => MatchKind::Word,
+        }
+    }
+}
+
+/// One blocklist row. Patterns are stored lower-cased.
+#[derive(Clone, Debug)]
+pub struct BlockedPattern {
+    pub pattern: String,
+    pub kind: MatchKind,
+    pub category: String,
+}
+
+impl fmt::Display for BlockedPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" ({}, {})",
+            self.pattern,
+            self.kind.as_str(),
+            self.category
+        )
+    }
+}
+
+/// The blocklist as loaded for filtering.
+pub struct Blocklist {
+    words: HashMap<String, BlockedPattern>,
+    substrings: Vec<BlockedPattern>,
+}
+
+impl Blocklist {
+    /// Reads the blocklist, keeping only patterns in `categories` if given.
+    pub fn load(conn: &Connection, categories: Option<&[String]>) -> Result<Self> {
+        let mut stmt =
+            conn.prepare_cached("SELECT pattern, kind, category FROM blocklist ORDER BY id")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut blocklist = Blocklist {
+            words: HashMap::new(),
+            substrings: Vec::new(),
+        };
+        for (pattern, kind, category) in rows {
+            if categories.is_some_and(|wanted| !wanted.contains(&category)) {
+                continue;
+            }
+            let kind = MatchKind::parse(&kind);
+            let blocked = BlockedPattern {
+                pattern: pattern.to_lowercase(),
+                kind,
+                category,
+            };
+            match kind {
+                MatchKind::Word => {
+                    blocklist.words.insert(blocked.pattern.clone(), blocked);
+                }
+                MatchKind::Substring => blocklist.substrings.push(blocked),
+            }
+        }
+        Ok(blocklist)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len() + self.substrings.len()
+    }
+
+    /// The first pattern `text` falls foul of, if any.
+    pub fn check(&self, text: &str) -> Option<&BlockedPattern> {
+        let text = text.to_lowercase();
+        text.split(|c: char| !c.is_alphanumeric())
+            .find_map(|word| self.words.get(word))
+            .or_else(|| {
+                self.substrings
+                    .iter()
+                    .find(|blocked| text.contains(&blocked.pattern))
+            })
+    }
+}
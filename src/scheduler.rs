@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use actix_web::web;
+use chrono::{Days, NaiveDate};
+
+use crate::config;
+use crate::db::Repo;
+use crate::error::{Error, Result};
+use crate::generation::{get_or_create_puzzle, load_todays_puzzle};
+
+/// How many days beyond today to keep generated unless configured
+/// otherwise.
+pub const DEFAULT_DAYS_AHEAD: u64 = 7;
+
+/// How often the background task checks for missing dates.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Makes sure a puzzle exists for `from` and each of the following
+/// `days_ahead` days. Returns the dates that had to be generated.
+pub fn fill_ahead(repo: &Repo, from: NaiveDate, days_ahead: u64) -> Result<Vec<String>> {
+    let mut generated = Vec::new();
+
+    for offset in 0..=days_ahead {
+        let date = from
+            .checked_add_days(Days::new(offset))
+            .ok_or_else(|| {
+                Error::Invalid(format!("date out of range: {} + {} days", from, offset))
+            })?
+            .format("%Y-%m-%d")
+            .to_string();
+
+        if load_todays_puzzle(repo, &date)?.is_some() {
+            continue;
+        }
+        get_or_create_puzzle(repo, &date)?;
+        generated.push(date);
+    }
+
+    Ok(generated)
+}
+
+/// Background task for the server: keeps the next `days_ahead` days
+/// generated, so no visitor has to wait for the generator.
+pub async fn run(repo: Repo, days_ahead: u64, interval: Duration) {
+    let mut ticker = actix_web::rt::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let today = config::get().puzzle.today();
+        let repo = repo.clone();
+        match web::block(move || fill_ahead(&repo, today, days_ahead)).await {
+            Ok(Ok(generated)) if !generated.is_empty() => {
+                log::info!("Scheduler generated puzzles for {}", generated.join(", "))
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => log::error!("Scheduler failed to generate puzzles: {}", e),
+            Err(e) => log::error!("Scheduler task failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A set of word indices, one bit per word in a `WordIndex`.
+#[derive(Clone, Debug)]
+struct Bitset {
+    blocks: Vec<u64>,
+}
+
+impl Bitset {
+    fn empty(len: usize) -> Self {
+        Bitset {
+            blocks: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.blocks[i / 64] |= 1 << (i % 64);
+    }
+
+    fn remove(&mut self, i: usize) {
+        self.blocks[i / 64] &= !(1 << (i % 64));
+    }
+
+    fn intersect(&mut self, other: &Bitset) {
+        for (a, b) in self.blocks.iter_mut().zip(&other.blocks) {
+            *a &= b;
+        }
+    }
+
+    fn subtract(&mut self, other: &Bitset) {
+        for (a, b) in self.blocks.iter_mut().zip(&other.blocks) {
+            *a &= !b;
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.blocks.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks.iter().enumerate().flat_map(|(i, &block)| {
+            let mut rest = block;
+            std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+/// All usable words, with a bitset per length and per (position, letter) so
+/// the candidates for a partially filled slot are a handful of ANDs.
+///
+/// Candidates come out in the order the words were given, so shuffling the
+/// input is what randomises the search.
+pub struct WordIndex {
+    words: Vec<Vec<u8>>,
+    by_length: HashMap<usize, Bitset>,
+    by_letter: Vec<Vec<Bitset>>,
+}
+
+impl WordIndex {
+    /// Builds an index of the distinct words in `words`. Words are
+    /// upper-cased; anything that isn't plain A-Z is skipped.
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut seen = HashSet::new();
+        let words: Vec<Vec<u8>> = words
+            .into_iter()
+            .map(|w| w.to_ascii_uppercase().into_bytes())
+            .filter(|w| !w.is_empty() && w.iter().all(u8::is_ascii_uppercase))
+            .filter(|w| seen.insert(w.clone()))
+            .collect();
+
+        let max_length = words.iter().map(Vec::len).max().unwrap_or(0);
+        let mut by_length: HashMap<usize, Bitset> = HashMap::new();
+        let mut by_letter = vec![vec![Bitset::empty(words.len()); 26]; max_length];
+        for (i, word) in words.iter().enumerate() {
+            by_length
+                .entry(word.len())
+                .or_insert_with(|| Bitset::empty(words.len()))
+                .insert(i);
+            for (pos, letter) in word.iter().enumerate() {
+                by_letter[pos][(letter - b'A') as usize].insert(i);
+            }
+        }
+
+        WordIndex {
+            words,
+            by_length,
+            by_letter,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn word(&self, i: usize) -> &str {
+        std::str::from_utf8(&self.words[i]).expect("index only holds ASCII words")
+    }
+
+    /// Words matching `pattern`, where `None` is an unconstrained position.
+    fn candidates(&self, pattern: &[Option<u8>]) -> Bitset {
+        let Some(mut set) = self.by_length.get(&pattern.len()).cloned() else {
+            return Bitset::empty(self.words.len());
+        };
+        for (pos, letter) in pattern.iter().enumerate() {
+            if let Some(letter) = letter {
+                set.intersect(&self.by_letter[pos][(letter - b'A') as usize]);
+            }
+        }
+        set
+    }
+}
+
+/// A run of cells that has to hold one word, as `(row, col)` pairs.
+#[derive(Clone, Debug)]
+pub struct Slot {
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// Limits on how long the search may run before giving up.
+#[derive(Clone, Copy, Debug)]
+pub struct Budget {
+    pub max_nodes: u64,
+    pub time_limit: Duration,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget {
+            max_nodes: 5_000_000,
+            time_limit: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolveError {
+    /// Every possibility was tried; no fill exists for this word list.
+    Exhausted { nodes: u64 },
+    /// The budget ran out before a fill was found or ruled out.
+    BudgetExceeded { nodes: u64 },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Exhausted { nodes } => {
+                write!(f, "no valid fill exists (searched {} nodes)", nodes)
+            }
+            SolveError::BudgetExceeded { nodes } => {
+                write!(f, "search budget exceeded after {} nodes", nodes)
+            }
+        }
+    }
+}
+
+/// Fills every slot of a `height` x `width` grid with distinct words from
+/// `index`, where crossing slots must agree on their shared cell.
+///
+/// Backtracking search that always fills the slot with the fewest remaining
+/// candidates next, and backs off as soon as any open slot has none left.
+/// Returns the chosen word (as an index into `index`) for each slot.
+pub fn solve(
+    index: &WordIndex,
+    slots: &[Slot],
+    width: usize,
+    height: usize,
+    budget: Budget,
+) -> Result<Vec<usize>, SolveError> {
+    let mut search = Search {
+        index,
+        slots,
+        width,
+        grid: vec![None; width * height],
+        assigned: vec![None; slots.len()],
+        used: Bitset::empty(index.len()),
+        nodes: 0,
+        budget,
+        started: Instant::now(),
+    };
+
+    match search.step() {
+        Step::Solved => Ok(search.assigned.into_iter().flatten().collect()),
+        Step::DeadEnd => Err(SolveError::Exhausted {
+            nodes: search.nodes,
+        }),
+        Step::OutOfBudget => Err(SolveError::BudgetExceeded {
+            nodes: search.nodes,
+        }),
+    }
+}
+
+enum Step {
+    Solved,
+    DeadEnd,
+    OutOfBudget,
+}
+
+struct Search<'a> {
+    index: &'a WordIndex,
+    slots: &'a [Slot],
+    width: usize,
+    grid: Vec<Option<u8>>,
+    assigned: Vec<Option<usize>>,
+    used: Bitset,
+    nodes: u64,
+    budget: Budget,
+    started: Instant,
+}
+
+impl Search<'_> {
+    fn step(&mut self) -> Step {
+        self.nodes += 1;
+        if self.nodes > self.budget.max_nodes
+            || (self.nodes.is_multiple_of(1024) && self.started.elapsed() > self.budget.time_limit)
+        {
+            return Step::OutOfBudget;
+        }
+
+        // Pick the open slot with the fewest candidates.
+        let mut best: Option<(usize, Bitset, usize)> = None;
+        for (slot_index, slot) in self.slots.iter().enumerate() {
+            if self.assigned[slot_index].is_some() {
+                continue;
+            }
+            let mut candidates = self.index.candidates(&self.pattern(slot));
+            candidates.subtract(&self.used);
+            let count = candidates.count();
+            if count == 0 {
+                return Step::DeadEnd;
+            }
+            if best
+                .as_ref()
+                .is_none_or(|(_, _, best_count)| count < *best_count)
+            {
+                best = Some((slot_index, candidates, count));
+            }
+        }
+
+        let Some((slot_index, candidates, _)) = best else {
+            return Step::Solved;
+        };
+
+        let slot = &self.slots[slot_index];
+        let previous = self.pattern(slot);
+        for word in candidates.iter() {
+            self.place(slot, word);
+            self.assigned[slot_index] = Some(word);
+            self.used.insert(word);
+
+            match self.step() {
+                Step::DeadEnd => {}
+                done => return done,
+            }
+
+            self.used.remove(word);
+            self.assigned[slot_index] = None;
+            self.restore(slot, &previous);
+        }
+
+        Step::DeadEnd
+    }
+
+    fn pattern(&self, slot: &Slot) -> Vec<Option<u8>> {
+        slot.cells
+            .iter()
+            .map(|&(row, col)| self.grid[row * self.width + col])
+            .collect()
+    }
+
+    fn place(&mut self, slot: &Slot, word: usize) {
+        let letters = self.index.word(word).as_bytes();
+        for (&(row, col), &letter) in slot.cells.iter().zip(letters) {
+            self.grid[row * self.width + col] = Some(letter);
+        }
+    }
+
+    fn restore(&mut self, slot: &Slot, previous: &[Option<u8>]) {
+        for (&(row, col), &letter) in slot.cells.iter().zip(previous) {
+            self.grid[row * self.width + col] = letter;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The two rows then the two columns of a 2x2 grid.
+    fn two_by_two() -> Vec<Slot> {
+        vec![
+            Slot {
+                cells: vec![(0, 0), (0, 1)],
+            },
+            Slot {
+                cells: vec![(1, 0), (1, 1)],
+            },
+            Slot {
+                cells: vec![(0, 0), (1, 0)],
+            },
+            Slot {
+                cells: vec![(0, 1), (1, 1)],
+            },
+        ]
+    }
+
+    #[test]
+    fn fills_crossing_slots_with_distinct_words() {
+        let index = WordIndex::new(["ab", "cd", "ac", "bd", "xyz"]);
+        let fill = solve(&index, &two_by_two(), 2, 2, Budget::default()).unwrap();
+        let words: Vec<&str> = fill.iter().map(|&i| index.word(i)).collect();
+        assert_eq!(words, ["AB", "CD", "AC", "BD"]);
+    }
+
+    #[test]
+    fn reports_a_grid_no_fill_exists_for() {
+        // The rows fit, but nothing reads down them.
+        let index = WordIndex::new(["AB", "CD"]);
+        let error = solve(&index, &two_by_two(), 2, 2, Budget::default()).unwrap_err();
+        assert!(matches!(error, SolveError::Exhausted { .. }), "{}", error);
+    }
+
+    #[test]
+    fn gives_up_when_the_node_budget_runs_out() {
+        let index = WordIndex::new(["AB", "CD", "AC", "BD"]);
+        let budget = Budget {
+            max_nodes: 2,
+            ..Budget::default()
+        };
+        assert_eq!(
+            solve(&index, &two_by_two(), 2, 2, budget),
+            Err(SolveError::BudgetExceeded { nodes: 3 })
+        );
+    }
+}
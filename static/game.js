@@ -4,10 +4,96 @@ import htm from "https://esm.sh/htm";
 
 const html = htm.bind(h);
 
+// Grid geometry. Puzzles come in different shapes, so everything is derived
+// from the puzzle itself. The plusword is drawn as an extra row below the
+// grid, so its row index is `puzzle.height`.
+
+function pluswordRow(puzzle) {
+  return puzzle ? puzzle.height : 5;
+}
+
+function isBlock(puzzle, row, col) {
+  return !!puzzle && row < puzzle.height && puzzle.grid[row][col] === '#';
+}
+
+function emptyGrid(puzzle) {
+  const rows = Array(puzzle.height).fill().map(() => Array(puzzle.width).fill(''));
+  rows.push(Array(puzzle.plusword_length).fill(''));
+  return rows;
+}
+
+function clueCells(clue, orientation) {
+  return Array.from({ length: clue.length }, (_, i) => orientation === 'across'
+    ? { row: clue.row, col: clue.col + i }
+    : { row: clue.row + i, col: clue.col });
+}
+
+function cluesFor(puzzle, orientation) {
+  return orientation === 'across' ? puzzle.across_clues : puzzle.down_clues;
+}
+
+// The clue running through a cell in the given direction, if any
+function clueAt(puzzle, orientation, row, col) {
+  if (!puzzle || row === null || row >= puzzle.height) return null;
+  return cluesFor(puzzle, orientation).find(clue => orientation === 'across'
+    ? clue.row === row && col >= clue.col && col < clue.col + clue.length
+    : clue.col === col && row >= clue.row && row < clue.row + clue.length) || null;
+}
+
+function cellNumber(puzzle, row, col) {
+  const clue = puzzle.across_clues.find(c => c.row === row && c.col === col)
+    || puzzle.down_clues.find(c => c.row === row && c.col === col);
+  return clue ? clue.number : null;
+}
+
+// The cell after (or before) this one when typing in `orientation`: along the
+// current clue, then on to the next clue, and from the last clue to the plusword.
+function stepCell(puzzle, orientation, row, col, delta) {
+  const plusRow = pluswordRow(puzzle);
+  if (row === plusRow) {
+    const nextCol = col + delta;
+    if (nextCol >= 0 && nextCol < puzzle.plusword_length) return { row, col: nextCol };
+    if (delta < 0) {
+      const clues = cluesFor(puzzle, orientation);
+      const cells = clueCells(clues[clues.length - 1], orientation);
+      return cells[cells.length - 1];
+    }
+    return null;
+  }
+
+  const clues = cluesFor(puzzle, orientation);
+  const clue = clueAt(puzzle, orientation, row, col);
+  if (!clue) return null;
+  const cells = clueCells(clue, orientation);
+  const index = cells.findIndex(c => c.row === row && c.col === col) + delta;
+  if (index >= 0 && index < cells.length) return cells[index];
+
+  const clueIndex = clues.indexOf(clue) + delta;
+  if (clueIndex < 0) return null;
+  if (clueIndex >= clues.length) return { row: plusRow, col: 0 };
+  const nextCells = clueCells(clues[clueIndex], orientation);
+  return delta > 0 ? nextCells[0] : nextCells[nextCells.length - 1];
+}
+
+// The nearest open cell from (row, col) in an arrow-key direction
+function arrowCell(puzzle, row, col, dRow, dCol) {
+  const plusRow = pluswordRow(puzzle);
+  let r = row + dRow;
+  let c = col + dCol;
+  while (r >= 0 && r <= plusRow && c >= 0) {
+    const width = r === plusRow ? puzzle.plusword_length : puzzle.width;
+    if (c >= width) return null;
+    if (!isBlock(puzzle, r, c)) return { row: r, col: c };
+    r += dRow;
+    c += dCol;
+  }
+  return null;
+}
+
 function App() {
   const [puzzle, setPuzzle] = useState(null);
   const [loading, setLoading] = useState(true);
-  const [gridData, setGridData] = useState(Array(6).fill().map(() => Array(5).fill(''))); // 6 rows: 0-4 regular, 5 plusword
+  const [gridData, setGridData] = useState([]); // grid rows, then the plusword row
   const [startTime, setStartTime] = useState(null);
   const [elapsed, setElapsed] = useState(0);
   const [currentMode, setCurrentMode] = useState('across');
@@ -65,16 +151,39 @@ function App() {
     };
   }, [startTime, isCompleted, isPaused]);
 
+  // The player's own date, which the server honours in client-local mode.
+  function localDate() {
+    const now = new Date();
+    const pad = (n) => String(n).padStart(2, '0');
+    return `${now.getFullYear()}-${pad(now.getMonth() + 1)}-${pad(now.getDate())}`;
+  }
+
   async function loadPuzzle() {
     try {
       setLoading(true);
-      const response = await fetch('/api/puzzle/today');
-      if (!response.ok) throw new Error('Failed to load puzzle');
+      const date = new URLSearchParams(window.location.search).get('date');
+      const url = date
+        ? `/api/puzzle/${encodeURIComponent(date)}`
+        : `/api/puzzle/today?date=${localDate()}`;
+      let response = await fetch(url);
+      // A puzzle that's being generated will be ready in a few seconds.
+      for (let attempt = 0; response.status === 503 && attempt < 10; attempt++) {
+        const body = await response.clone().json().catch(() => ({}));
+        if (body.error !== 'generating') break;
+        const seconds = Number(response.headers.get('Retry-After')) || 5;
+        await new Promise(resolve => setTimeout(resolve, seconds * 1000));
+        response = await fetch(url);
+      }
+      if (!response.ok) {
+        const body = await response.json().catch(() => ({}));
+        throw new Error(body.message || 'Failed to load puzzle');
+      }
       const data = await response.json();
+      setGridData(emptyGrid(data));
       setPuzzle(data);
     } catch (error) {
       console.error('Error loading puzzle:', error);
-      showModal('❌', 'Error', 'Failed to load puzzle. Please refresh the page.');
+      showModal('❌', 'Error', `${error.message}. Please refresh the page.`);
     } finally {
       setLoading(false);
     }
@@ -99,36 +208,18 @@ function App() {
     }
   }
 
-  function validatePuzzle(currentGridData) {
-    if (!puzzle) return false;
-
-    // Check across words (rows 0-4)
-    for (let row = 0; row < puzzle.across_words.length; row++) {
-      const across = puzzle.across_words[row];
-      for (let col = 0; col < across.word.length; col++) {
-        if (currentGridData[row][col].toUpperCase() !== across.word[col].toUpperCase()) {
-          return false;
-        }
-      }
-    }
-
-    // Check down words (rows 0-4)
-    for (let col = 0; col < puzzle.down_words.length; col++) {
-      const down = puzzle.down_words[col];
-      for (let row = 0; row < down.word.length; row++) {
-        if (currentGridData[row][col].toUpperCase() !== down.word[row].toUpperCase()) {
-          return false;
-        }
-      }
-    }
-
-    // Check plusword (row 5)
-    const pluswordFromGrid = currentGridData[5].join('');
-    if (pluswordFromGrid.toUpperCase() !== puzzle.plusword.toUpperCase()) {
-      return false;
-    }
-
-    return true;
+  async function checkPuzzle(currentGridData) {
+    const response = await fetch('/api/puzzle/check', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({
+        date: puzzle.date,
+        grid: currentGridData.slice(0, puzzle.height),
+        plusword: currentGridData[puzzle.height],
+      }),
+    });
+    if (!response.ok) throw new Error('Failed to check puzzle');
+    return response.json();
   }
 
   // Check for completion whenever grid changes
@@ -136,22 +227,24 @@ function App() {
     // Don't check if modal is already visible, puzzle not loaded, or result already shown
     if (!puzzle || modal.visible || hasShownResult) return;
 
-    const allGridFilled = gridData.every(row => row.every(cell => cell !== ''));
+    const allGridFilled = gridData.every((row, r) => row.every((cell, c) => cell !== '' || isBlock(puzzle, r, c)));
 
     if (allGridFilled) {
-      const success = validatePuzzle(gridData);
-
-      if (success) {
-        setIsCompleted(true); // Stop the timer permanently
-        const minutes = Math.floor(elapsed / 60);
-        const seconds = elapsed % 60;
-        const timeStr = `${minutes}:${String(seconds).padStart(2, '0')}`;
-        showModal('🎉', 'Congratulations!', `You solved the puzzle in ${timeStr}!`, timeStr);
-        setHasShownResult(true);
-      } else {
-        showModal('❌', 'Not Quite!', 'At least one letter is wrong. Keep trying!');
-        setHasShownResult(true);
-      }
+      setHasShownResult(true);
+      checkPuzzle(gridData).then(result => {
+        if (result.solved) {
+          setIsCompleted(true); // Stop the timer permanently
+          const minutes = Math.floor(elapsed / 60);
+          const seconds = elapsed % 60;
+          const timeStr = `${minutes}:${String(seconds).padStart(2, '0')}`;
+          showModal('🎉', 'Congratulations!', `You solved the puzzle in ${timeStr}!`, timeStr);
+        } else {
+          showModal('❌', 'Not Quite!', 'At least one letter is wrong. Keep trying!');
+        }
+      }).catch(error => {
+        console.error('Error checking puzzle:', error);
+        showModal('❌', 'Error', 'Failed to check your answers. Please try again.');
+      });
     }
   }, [gridData, puzzle, modal.visible, elapsed, hasShownResult]);
 
@@ -172,36 +265,26 @@ function App() {
     }
   }
 
+  function moveTo(cell) {
+    if (!cell) return;
+    setActiveCell(cell);
+    setTimeout(() => focusCell(cell.row, cell.col), 0);
+  }
+
   // Get ordered list of clues (all across, then all down)
   function getClueList() {
     if (!puzzle) return [];
-    const clues = [];
-    // Across clues (0-4)
-    for (let i = 0; i < puzzle.across_words.length; i++) {
-      clues.push({ orientation: 'across', position: i });
-    }
-    // Down clues (0-4)
-    for (let i = 0; i < puzzle.down_words.length; i++) {
-      clues.push({ orientation: 'down', position: i });
-    }
-    return clues;
+    return [
+      ...puzzle.across_clues.map(clue => ({ orientation: 'across', clue })),
+      ...puzzle.down_clues.map(clue => ({ orientation: 'down', clue })),
+    ];
   }
 
   // Find current clue index based on activeCell and currentMode
   function getCurrentClueIndex() {
-    const clues = getClueList();
-    if (activeCell.row === null || activeCell.row === 5) return -1;
-
-    for (let i = 0; i < clues.length; i++) {
-      const clue = clues[i];
-      if (clue.orientation === 'across' && currentMode === 'across' && clue.position === activeCell.row) {
-        return i;
-      }
-      if (clue.orientation === 'down' && currentMode === 'down' && clue.position === activeCell.col) {
-        return i;
-      }
-    }
-    return -1;
+    const current = clueAt(puzzle, currentMode, activeCell.row, activeCell.col);
+    if (!current) return -1;
+    return getClueList().findIndex(item => item.clue === current);
   }
 
   // Navigate to next/previous clue
@@ -219,23 +302,17 @@ function App() {
       nextIndex = (currentIndex - 1 + clues.length) % clues.length;
     }
 
-    const nextClue = clues[nextIndex];
-    setCurrentMode(nextClue.orientation);
-    if (nextClue.orientation === 'across') {
-      setActiveCell({ row: nextClue.position, col: 0 });
-      setTimeout(() => focusCell(nextClue.position, 0), 0);
-    } else {
-      setActiveCell({ row: 0, col: nextClue.position });
-      setTimeout(() => focusCell(0, nextClue.position), 0);
-    }
+    const next = clues[nextIndex];
+    setCurrentMode(next.orientation);
+    moveTo({ row: next.clue.row, col: next.clue.col });
   }
 
   // Handle on-screen keyboard input
   function handleOnScreenKey(key) {
     if (activeCell.row === null) {
       // Focus first cell if none active
-      setActiveCell({ row: 0, col: 0 });
-      setTimeout(() => focusCell(0, 0), 0);
+      const first = puzzle.across_clues[0];
+      moveTo({ row: first.row, col: first.col });
       return;
     }
 
@@ -252,32 +329,10 @@ function App() {
       const currentValue = gridData[row][col];
       if (!currentValue) {
         // Move back and delete
-        if (row === 5) {
-          if (col > 0) {
-            handleGridCellChange(row, col - 1, '');
-            setActiveCell({ row, col: col - 1 });
-            setTimeout(() => focusCell(row, col - 1), 0);
-          }
-        } else if (currentMode === 'across') {
-          if (col > 0) {
-            handleGridCellChange(row, col - 1, '');
-            setActiveCell({ row, col: col - 1 });
-            setTimeout(() => focusCell(row, col - 1), 0);
-          } else if (row > 0) {
-            handleGridCellChange(row - 1, 4, '');
-            setActiveCell({ row: row - 1, col: 4 });
-            setTimeout(() => focusCell(row - 1, 4), 0);
-          }
-        } else {
-          if (row > 0) {
-            handleGridCellChange(row - 1, col, '');
-            setActiveCell({ row: row - 1, col });
-            setTimeout(() => focusCell(row - 1, col), 0);
-          } else if (col > 0) {
-            handleGridCellChange(4, col - 1, '');
-            setActiveCell({ row: 4, col: col - 1 });
-            setTimeout(() => focusCell(4, col - 1), 0);
-          }
+        const prev = stepCell(puzzle, currentMode, row, col, -1);
+        if (prev) {
+          handleGridCellChange(prev.row, prev.col, '');
+          moveTo(prev);
         }
       } else {
         handleGridCellChange(row, col, '');
@@ -289,50 +344,24 @@ function App() {
     handleGridCellChange(row, col, key);
 
     // Auto-advance
-    let nextRow = row;
-    let nextCol = col;
-    if (row === 5) {
-      if (col < 4) nextCol = col + 1;
-    } else if (currentMode === 'across') {
-      if (col < 4) {
-        nextCol = col + 1;
-      } else if (row < 4) {
-        nextRow = row + 1;
-        nextCol = 0;
-      } else {
-        nextRow = 5;
-        nextCol = 0;
-      }
-    } else {
-      if (row < 4) {
-        nextRow = row + 1;
-      } else if (col < 4) {
-        nextRow = 0;
-        nextCol = col + 1;
-      } else {
-        nextRow = 5;
-        nextCol = 0;
-      }
-    }
-
-    if (nextRow !== row || nextCol !== col) {
-      setActiveCell({ row: nextRow, col: nextCol });
-      setTimeout(() => focusCell(nextRow, nextCol), 0);
-    }
+    moveTo(stepCell(puzzle, currentMode, row, col, 1));
   }
 
   // Handle touch/click for mode toggle
   function handleCellTap(row, col) {
-    if (row === 5) {
+    if (row === pluswordRow(puzzle)) {
       // Plusword row - always horizontal
       setPrevClickedCell({ row, col });
       setActiveCell({ row, col });
       return;
     }
 
-    // Toggle mode only if tapping the same cell that was previously clicked
-    if (prevClickedCell && prevClickedCell.row === row && prevClickedCell.col === col) {
-      setCurrentMode(currentMode === 'across' ? 'down' : 'across');
+    // Toggle mode only if tapping the same cell that was previously clicked,
+    // and only if there's a clue running the other way through it
+    const otherMode = currentMode === 'across' ? 'down' : 'across';
+    const isSameCell = prevClickedCell && prevClickedCell.row === row && prevClickedCell.col === col;
+    if ((isSameCell || !clueAt(puzzle, currentMode, row, col)) && clueAt(puzzle, otherMode, row, col)) {
+      setCurrentMode(otherMode);
     }
     setPrevClickedCell({ row, col });
     setActiveCell({ row, col });
@@ -340,13 +369,9 @@ function App() {
 
   // Get clue text for current active cell
   function getClueText() {
-    if (!puzzle || activeCell.row === null || activeCell.row === 5) return '';
-    if (currentMode === 'across' && puzzle.across_words?.[activeCell.row]) {
-      return `${activeCell.row + 1}A. ${puzzle.across_words[activeCell.row].clue}`;
-    } else if (currentMode === 'down' && puzzle.down_words?.[activeCell.col]) {
-      return `${activeCell.col + 1}D. ${puzzle.down_words[activeCell.col].clue}`;
-    }
-    return '';
+    const clue = clueAt(puzzle, currentMode, activeCell.row, activeCell.col);
+    if (!clue) return '';
+    return `${clue.number}${currentMode === 'across' ? 'A' : 'D'}. ${clue.clue}`;
   }
 
   const minutes = Math.floor(elapsed / 60);
@@ -356,7 +381,7 @@ function App() {
   if (loading) {
     return html`
       <div class="max-w-7xl mx-auto">
-        <${Header} puzzleNumber="1328" />
+        <${Header} puzzleNumber="" />
         <div class="flex items-center justify-center min-h-[400px]">
           <div class="flex flex-col items-center gap-4">
             <div class="w-16 h-16 border-4 border-gray-300 border-t-gray-900 rounded-full animate-spin"></div>
@@ -369,7 +394,7 @@ function App() {
 
   return html`
     <div class="max-w-7xl mx-auto">
-      <${Header} puzzleNumber="1328" />
+      <${Header} puzzleNumber=${puzzle?.number ?? ''} difficulty=${puzzle?.difficulty} />
 
       <main class="max-w-7xl mx-auto">
         <div class="grid md:grid-cols-2 gap-8 items-start">
@@ -388,7 +413,7 @@ function App() {
               cellRefsFromGrid=${cellRefsFromGrid}
               navigateClue=${navigateClue}
             />
-            ${activeCell.row !== null && activeCell.row !== 5 && html`
+            ${activeCell.row !== null && activeCell.row !== pluswordRow(puzzle) && html`
               <div class="w-full flex justify-center hidden md:flex">
                 <div class="bg-gray-100 rounded-lg px-4 py-2" style="width: min(90vw, 400px);">
                   <${SelectedClue} puzzle=${puzzle} activeCell=${activeCell} currentMode=${currentMode} />
@@ -401,17 +426,10 @@ function App() {
 
           <${Clues}
             puzzle=${puzzle}
-            onClueClick=${(orientation, position) => {
+            onClueClick=${(orientation, clue) => {
       setCurrentMode(orientation);
-      if (orientation === 'across') {
-        setActiveCell({ row: position, col: 0 });
-        // Focus the first cell of the across word after a short delay
-        setTimeout(() => focusCell(position, 0), 0);
-      } else {
-        setActiveCell({ row: 0, col: position });
-        // Focus the first cell of the down word after a short delay
-        setTimeout(() => focusCell(0, position), 0);
-      }
+      // Focus the first cell of the word after a short delay
+      moveTo({ row: clue.row, col: clue.col });
     }}
             activeCell=${activeCell}
             currentMode=${currentMode}
@@ -421,7 +439,7 @@ function App() {
 
       <${OnScreenKeyboard}
         onKeyPress=${handleOnScreenKey}
-        clueText=${activeCell.row !== null && activeCell.row !== 5 ? getClueText() : null}
+        clueText=${activeCell.row !== null && activeCell.row !== pluswordRow(puzzle) ? getClueText() : null}
         onPrevClue=${() => navigateClue('prev')}
         onNextClue=${() => navigateClue('next')}
       />
@@ -440,18 +458,23 @@ function App() {
   `;
 }
 
-function Header({ puzzleNumber }) {
+function Header({ puzzleNumber, difficulty }) {
   return html`
     <header class="text-center py-2 md:py-6">
       <div class="inline-block bg-gray-900 text-white px-4 py-1.5 md:px-8 md:py-3 rounded-lg mb-2 md:mb-4">
         <h1 class="text-base md:text-2xl font-bold tracking-wider">QUINTA NO. ${puzzleNumber}</h1>
       </div>
+      <div class="text-sm text-gray-600">
+        ${difficulty != null && html`<span class="mr-3">Difficulty ${difficulty.toFixed(1)} / 5</span>`}
+        <a href="/?archive" class="hover:text-gray-900 underline">Archive</a>
+      </div>
     </header>
   `;
 }
 
 function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange, onCellFocus, onCellTap, setCurrentMode, cellRefsFromGrid, navigateClue }) {
   const cellRefs = useRef({});
+  const plusRow = pluswordRow(puzzle);
 
   // Share cellRefs with parent component
   useEffect(() => {
@@ -461,7 +484,7 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
   }, [cellRefsFromGrid]);
 
   function getHintClass(row, col) {
-    if (!puzzle || row === 5) return ''; // No hints for plusword row
+    if (!puzzle || row === plusRow) return ''; // No hints for plusword row
 
     let hint = null;
 
@@ -481,30 +504,37 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
 
     const style = {};
 
-    // For plusword (row 5), highlight all cells
-    if (row === 5 && activeCell.row === 5) {
+    // For plusword, highlight all cells
+    if (row === plusRow && activeCell.row === plusRow) {
       style.borderTop = '3px solid black';
       style.borderBottom = '3px solid black';
       if (col === 0) style.borderLeft = '3px solid black';
-      if (col === 4) style.borderRight = '3px solid black';
-    } else if (row !== 5) {
-      // Regular grid behavior (rows 0-4)
-      if (currentMode === 'across' && activeCell.row === row) {
-        style.borderTop = '3px solid black';
-        style.borderBottom = '3px solid black';
-        if (col === 0) style.borderLeft = '3px solid black';
-        if (col === 4) style.borderRight = '3px solid black';
-      } else if (currentMode === 'down' && activeCell.col === col) {
-        style.borderLeft = '3px solid black';
-        style.borderRight = '3px solid black';
-        if (row === 0) style.borderTop = '3px solid black';
-        if (row === 4) style.borderBottom = '3px solid black';
+      if (col === puzzle.plusword_length - 1) style.borderRight = '3px solid black';
+    } else if (row !== plusRow) {
+      // Regular grid: outline the active clue
+      const clue = clueAt(puzzle, currentMode, activeCell.row, activeCell.col);
+      if (clue && clue === clueAt(puzzle, currentMode, row, col)) {
+        if (currentMode === 'across') {
+          style.borderTop = '3px solid black';
+          style.borderBottom = '3px solid black';
+          if (col === clue.col) style.borderLeft = '3px solid black';
+          if (col === clue.col + clue.length - 1) style.borderRight = '3px solid black';
+        } else {
+          style.borderLeft = '3px solid black';
+          style.borderRight = '3px solid black';
+          if (row === clue.row) style.borderTop = '3px solid black';
+          if (row === clue.row + clue.length - 1) style.borderBottom = '3px solid black';
+        }
       }
     }
 
     return style;
   }
 
+  function focus(cell) {
+    if (cell) cellRefs.current[`${cell.row}-${cell.col}`]?.focus();
+  }
+
   function handleKeyDown(e, row, col) {
     // Handle Tab / Shift+Tab for clue navigation
     if (e.key === 'Tab') {
@@ -521,104 +551,51 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
       e.preventDefault();
       onCellChange(row, col, e.key);
 
-      // Auto-advance
-      if (row === 5) {
-        // Plusword row - move right
-        if (col < 4) {
-          cellRefs.current[`${row}-${col + 1}`]?.focus();
-        }
-      } else if (currentMode === 'across') {
-        if (col < 4) {
-          const key = `${row}-${col + 1}`;
-          cellRefs.current[key]?.focus();
-        } else if (row < 4) {
-          const key = `${row + 1}-0`;
-          cellRefs.current[key]?.focus();
-        } else {
-          // At last cell of grid (row 4, col 4), move to plusword
-          cellRefs.current[`5-0`]?.focus();
-        }
-      } else {
-        if (row < 4) {
-          const key = `${row + 1}-${col}`;
-          cellRefs.current[key]?.focus();
-        } else if (col < 4) {
-          const key = `0-${col + 1}`;
-          cellRefs.current[key]?.focus();
-        } else {
-          // At last cell of grid, move to plusword
-          cellRefs.current[`5-0`]?.focus();
-        }
-      }
-
+      // Auto-advance along the clue, then on to the next one and the plusword
+      focus(stepCell(puzzle, currentMode, row, col, 1));
       return;
     }
 
+    // Arrow keys set the typing direction, if a clue runs that way
+    const setModeAt = (mode, cell) => {
+      if (cell && cell.row !== plusRow && clueAt(puzzle, mode, cell.row, cell.col)) setCurrentMode(mode);
+    };
+
     switch (e.key) {
       case 'ArrowRight':
-        if (col < 4) {
+      case 'ArrowLeft': {
+        const cell = arrowCell(puzzle, row, col, 0, e.key === 'ArrowRight' ? 1 : -1);
+        if (cell) {
           e.preventDefault();
-          if (row !== 5) setCurrentMode('across');
-          cellRefs.current[`${row}-${col + 1}`]?.focus();
-        }
-        break;
-      case 'ArrowLeft':
-        if (col > 0) {
-          e.preventDefault();
-          if (row !== 5) setCurrentMode('across');
-          cellRefs.current[`${row}-${col - 1}`]?.focus();
+          setModeAt('across', cell);
+          focus(cell);
         }
         break;
+      }
       case 'ArrowDown':
-        e.preventDefault();
-        if (row < 5) {
-          if (row < 4) {
-            setCurrentMode('down');
-          } else {
+      case 'ArrowUp': {
+        const cell = arrowCell(puzzle, row, col, e.key === 'ArrowDown' ? 1 : -1, 0);
+        if (cell) {
+          e.preventDefault();
+          if (cell.row === plusRow) {
             setCurrentMode('across');
+          } else {
+            setModeAt('down', cell);
           }
-          cellRefs.current[`${row + 1}-${col}`]?.focus();
-        }
-        break;
-      case 'ArrowUp':
-        if (row > 0) {
-          e.preventDefault();
-          if (row <= 5) setCurrentMode('down');
-          cellRefs.current[`${row - 1}-${col}`]?.focus();
+          focus(cell);
         }
         break;
+      }
       case 'Backspace':
         e.preventDefault();
         const currentValue = gridData[row][col];
 
         // If current cell is empty, move back and delete previous cell
         if (!currentValue) {
-          if (row === 5) {
-            // Plusword row - move left
-            if (col > 0) {
-              onCellChange(row, col - 1, '');
-              cellRefs.current[`${row}-${col - 1}`]?.focus();
-            } else {
-              // Move to last cell of regular grid
-              onCellChange(4, 4, '');
-              cellRefs.current[`4-4`]?.focus();
-            }
-          } else if (currentMode === 'across') {
-            if (col > 0) {
-              onCellChange(row, col - 1, '');
-              cellRefs.current[`${row}-${col - 1}`]?.focus();
-            } else if (row > 0) {
-              onCellChange(row - 1, 4, '');
-              cellRefs.current[`${row - 1}-4`]?.focus();
-            }
-          } else {
-            if (row > 0) {
-              onCellChange(row - 1, col, '');
-              cellRefs.current[`${row - 1}-${col}`]?.focus();
-            } else if (col > 0) {
-              onCellChange(4, col - 1, '');
-              cellRefs.current[`4-${col - 1}`]?.focus();
-            }
+          const prev = stepCell(puzzle, currentMode, row, col, -1);
+          if (prev) {
+            onCellChange(prev.row, prev.col, '');
+            focus(prev);
           }
         } else {
           // If current cell has value, just delete it (stay in place)
@@ -629,18 +606,24 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
   }
 
   const regularCells = [];
-  // Regular grid cells (rows 0-4)
-  for (let row = 0; row < 5; row++) {
-    for (let col = 0; col < 5; col++) {
+  // Regular grid cells
+  for (let row = 0; row < puzzle.height; row++) {
+    for (let col = 0; col < puzzle.width; col++) {
       const key = `${row}-${col}`;
+
+      if (isBlock(puzzle, row, col)) {
+        regularCells.push(html`<div class="bg-black border border-black" key=${key}></div>`);
+        continue;
+      }
+
       const hintClass = getHintClass(row, col);
       const borderStyle = getBorderStyle(row, col);
       const isActive = activeCell.row === row && activeCell.col === col;
+      const number = cellNumber(puzzle, row, col);
 
       regularCells.push(html`
         <div class="cell-wrapper relative" key=${key}>
-          ${col === 0 && html`<div class="cell-number">${row + 1}</div>`}
-          ${row === 0 && col > 0 && html`<div class="cell-number">${col + 1}</div>`}
+          ${number !== null && html`<div class="cell-number">${number}</div>`}
           <input
             ref=${el => cellRefs.current[key] = el}
             type="text"
@@ -665,12 +648,12 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
     }
   }
 
-  // Plusword cells (row 5)
+  // Plusword cells
   const pluswordCells = [];
-  for (let col = 0; col < 5; col++) {
-    const key = `5-${col}`;
-    const borderStyle = getBorderStyle(5, col);
-    const isActive = activeCell.row === 5 && activeCell.col === col;
+  for (let col = 0; col < puzzle.plusword_length; col++) {
+    const key = `${plusRow}-${col}`;
+    const borderStyle = getBorderStyle(plusRow, col);
+    const isActive = activeCell.row === plusRow && activeCell.col === col;
 
     pluswordCells.push(html`
       <input
@@ -679,39 +662,45 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
         type="text"
         inputmode="none"
         maxLength="1"
-        value=${gridData[5][col]}
+        value=${gridData[plusRow][col]}
         class="w-full h-full text-center text-3xl font-bold uppercase bg-white border border-black focus:outline-none ${isActive ? 'hatch' : ''}"
         style=${{ caretColor: 'transparent', ...borderStyle }}
-        data-row="5"
+        data-row=${plusRow}
         data-col=${col}
         onInput=${(e) => {
         // Prevent input changes - we handle everything in onKeyDown
         e.preventDefault();
         e.stopPropagation();
-        const currentVal = gridData[5][col];
+        const currentVal = gridData[plusRow][col];
         if (e.target.value !== currentVal) {
           e.target.value = currentVal;
         }
       }}
-        onKeyDown=${(e) => handleKeyDown(e, 5, col)}
-        onFocus=${() => onCellFocus(5, col)}
-        onClick=${() => onCellTap(5, col)}
+        onKeyDown=${(e) => handleKeyDown(e, plusRow, col)}
+        onFocus=${() => onCellFocus(plusRow, col)}
+        onClick=${() => onCellTap(plusRow, col)}
         onTouchEnd=${(e) => {
         e.preventDefault();
-        onCellTap(5, col);
+        onCellTap(plusRow, col);
         cellRefs.current[key]?.focus();
       }}
       />
     `);
   }
 
+  const gridStyle = {
+    gridTemplateColumns: `repeat(${puzzle.width}, minmax(0, 1fr))`,
+    aspectRatio: `${puzzle.width} / ${puzzle.height}`,
+  };
+  const pluswordStyle = { gridTemplateColumns: `repeat(${puzzle.plusword_length}, minmax(0, 1fr))` };
+
   return html`
     <div class="flex flex-col items-center gap-4 md:gap-8 mb-4 md:mb-8">
-      <div class="crossword-grid inline-grid grid-cols-5 gap-0 border-2 border-black">
+      <div class="crossword-grid inline-grid gap-0 border-2 border-black" style=${gridStyle}>
         ${regularCells}
       </div>
       <div class="w-full flex justify-center">
-        <div class="h-14 md:h-20 plusword-grid inline-grid grid-cols-5 gap-0 border-2 border-black">
+        <div class="h-14 md:h-20 plusword-grid inline-grid gap-0 border-2 border-black" style=${pluswordStyle}>
           ${pluswordCells}
         </div>
       </div>
@@ -720,16 +709,8 @@ function CrosswordGrid({ gridData, puzzle, currentMode, activeCell, onCellChange
 }
 
 function SelectedClue({ puzzle, activeCell, currentMode }) {
-  if (!puzzle || activeCell.row === null || activeCell.col === null) {
-    return html`<p class="text-lg font-semibold text-gray-800 mt-4 text-center"></p>`;
-  }
-
-  let clueText = '';
-  if (currentMode === 'across' && puzzle.across_words?.[activeCell.row]) {
-    clueText = `${activeCell.row + 1}. ${puzzle.across_words[activeCell.row].clue}`;
-  } else if (currentMode === 'down' && puzzle.down_words?.[activeCell.col]) {
-    clueText = `${activeCell.col + 1}. ${puzzle.down_words[activeCell.col].clue}`;
-  }
+  const clue = clueAt(puzzle, currentMode, activeCell.row, activeCell.col);
+  const clueText = clue ? `${clue.number}. ${clue.clue}` : '';
 
   return html`
     <p class="text-lg font-semibold text-gray-800 mt-4 text-center">${clueText}</p>
@@ -739,42 +720,36 @@ function SelectedClue({ puzzle, activeCell, currentMode }) {
 function Clues({ puzzle, onClueClick, activeCell, currentMode }) {
   if (!puzzle) return null;
 
-  return html`
-    <div class="space-y-6 hidden md:block">
-      <div>
-        <h2 class="text-2xl font-bold mb-4">Across</h2>
-        <div class="space-y-2">
-          ${puzzle.across_words.map((wordData, index) => {
-    const isActive = currentMode === 'across' && activeCell.row === index;
-    return html`
+  const activeClue = clueAt(puzzle, currentMode, activeCell.row, activeCell.col);
+
+  function clueList(orientation) {
+    return cluesFor(puzzle, orientation).map(clue => {
+      const isActive = clue === activeClue;
+      return html`
               <div
-                key=${index}
+                key=${clue.number}
                 class="p-2 hover:bg-gray-100 rounded cursor-pointer ${isActive ? 'bg-blue-200 font-bold' : ''}"
-                onClick=${() => onClueClick('across', index)}
+                onClick=${() => onClueClick(orientation, clue)}
               >
-                <span class="font-bold mr-2">${index + 1}.</span>
-                <span>${wordData.clue}</span>
+                <span class="font-bold mr-2">${clue.number}.</span>
+                <span>${clue.clue}</span>
               </div>
             `;
-  })}
+    });
+  }
+
+  return html`
+    <div class="space-y-6 hidden md:block">
+      <div>
+        <h2 class="text-2xl font-bold mb-4">Across</h2>
+        <div class="space-y-2">
+          ${clueList('across')}
         </div>
       </div>
       <div>
         <h2 class="text-2xl font-bold mb-4">Down</h2>
         <div class="space-y-2">
-          ${puzzle.down_words.map((wordData, index) => {
-    const isActive = currentMode === 'down' && activeCell.col === index;
-    return html`
-              <div
-                key=${index}
-                class="p-2 hover:bg-gray-100 rounded cursor-pointer ${isActive ? 'bg-blue-200 font-bold' : ''}"
-                onClick=${() => onClueClick('down', index)}
-              >
-                <span class="font-bold mr-2">${index + 1}.</span>
-                <span>${wordData.clue}</span>
-              </div>
-            `;
-  })}
+          ${clueList('down')}
         </div>
       </div>
     </div>
@@ -960,5 +935,68 @@ function Modal({ visible, icon, title, message, timeStr, copyButtonText, onClose
   `;
 }
 
-// Render the app
-render(html`<${App} />`, document.getElementById('root'));
+function Archive() {
+  const [page, setPage] = useState(1);
+  const [list, setList] = useState(null);
+  const [error, setError] = useState(false);
+
+  useEffect(() => {
+    setError(false);
+    fetch(`/api/puzzles?page=${page}`)
+      .then(response => {
+        if (!response.ok) throw new Error('Failed to load archive');
+        return response.json();
+      })
+      .then(setList)
+      .catch(err => {
+        console.error('Error loading archive:', err);
+        setError(true);
+      });
+  }, [page]);
+
+  const lastPage = list ? Math.max(1, Math.ceil(list.total / list.per_page)) : 1;
+
+  return html`
+    <div class="max-w-md mx-auto">
+      <header class="text-center py-2 md:py-6">
+        <div class="inline-block bg-gray-900 text-white px-4 py-1.5 md:px-8 md:py-3 rounded-lg mb-2 md:mb-4">
+          <h1 class="text-base md:text-2xl font-bold tracking-wider">QUINTA ARCHIVE</h1>
+        </div>
+        <div>
+          <a href="/" class="text-sm text-gray-600 hover:text-gray-900 underline">Today's puzzle</a>
+        </div>
+      </header>
+
+      ${error && html`<p class="text-center text-gray-600">Failed to load the archive. Please refresh the page.</p>`}
+      ${list && html`
+        <ul class="divide-y divide-gray-200 bg-white rounded-lg border border-gray-200">
+          ${list.puzzles.map(p => html`
+            <li key=${p.date}>
+              <a href="/?date=${p.date}" class="flex justify-between px-4 py-3 hover:bg-gray-100">
+                <span class="font-bold">No. ${p.number}</span>
+                <span class="text-gray-600">${p.date}</span>
+              </a>
+            </li>
+          `)}
+        </ul>
+        <div class="flex justify-between items-center mt-4">
+          <button
+            class="px-4 py-2 rounded-full bg-gray-900 text-white disabled:opacity-30"
+            disabled=${page <= 1}
+            onClick=${() => setPage(page - 1)}
+          >Newer</button>
+          <span class="text-gray-600">Page ${page} of ${lastPage}</span>
+          <button
+            class="px-4 py-2 rounded-full bg-gray-900 text-white disabled:opacity-30"
+            disabled=${page >= lastPage}
+            onClick=${() => setPage(page + 1)}
+          >Older</button>
+        </div>
+      `}
+    </div>
+  `;
+}
+
+// Render the archive or the game, depending on the URL
+const isArchive = new URLSearchParams(window.location.search).has('archive');
+render(isArchive ? html`<${Archive} />` : html`<${App} />`, document.getElementById('root'));